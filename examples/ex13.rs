@@ -0,0 +1,26 @@
+struct A {
+    b: Box<B>,
+}
+struct B {
+    a: Option<A>,
+}
+struct C {
+    d: D,
+}
+struct D {
+    c: Option<C>,
+}
+struct E {
+    e: std::rc::Rc<E>,
+}
+struct F(G);
+struct G;
+struct H {
+    i: self::I,
+}
+struct I {
+    h: crate::H,
+}
+struct J {
+    j: Option<Self>,
+}
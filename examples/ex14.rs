@@ -0,0 +1,28 @@
+trait Shape {
+    fn area(&self) -> f64;
+}
+trait Named {}
+struct Circle {
+    r: f64,
+}
+enum Polygon {
+    Triangle([Point; 3]),
+    Square { corner: Point, side: f64 },
+    Empty,
+}
+struct Point(f64, f64);
+struct Canvas<S: Shape> {
+    shapes: Vec<S>,
+    origin: Point,
+}
+impl Shape for Circle {
+    fn area(&self) -> f64 {
+        3.14 * self.r * self.r
+    }
+}
+impl Shape for Polygon {
+    fn area(&self) -> f64 {
+        0.0
+    }
+}
+impl Named for Circle {}
@@ -0,0 +1,32 @@
+use net::proto::Frame;
+use net::Client;
+
+struct App {
+    client: Client,
+    frames: Vec<Frame>,
+    config: Config,
+}
+
+struct Config;
+
+mod net {
+    use self::proto::Frame;
+    use super::Config;
+
+    pub struct Client {
+        conn: Connection,
+        config: Config,
+    }
+
+    pub struct Connection {
+        pending: Option<Frame>,
+    }
+
+    pub mod proto {
+        pub struct Frame {
+            header: Header,
+        }
+
+        pub struct Header;
+    }
+}
@@ -0,0 +1,14 @@
+struct User {
+    name: String,
+    account: Account,
+}
+
+struct Account {
+    balance: u64,
+}
+
+struct Session {
+    user: User,
+}
+
+trait Store {}
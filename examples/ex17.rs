@@ -0,0 +1,21 @@
+struct User {
+    name: String,
+    account: Account,
+    role: Role,
+}
+
+enum Account {
+    Free,
+    Paid { balance: u64 },
+}
+
+enum Role {
+    Admin,
+    Member,
+}
+
+trait Store {}
+
+struct Cache<S: Store> {
+    store: S,
+}
@@ -0,0 +1,24 @@
+mod domain {
+    use crate::infra::Mailer;
+
+    pub struct Customer {
+        email: String,
+    }
+
+    pub struct Order {
+        customer: Customer,
+        mailer: Mailer,
+    }
+}
+
+mod infra {
+    pub struct Mailer {
+        pool: sqlx::PgPool,
+    }
+}
+
+mod db {
+    pub struct Store {
+        pool: sqlx::PgPool,
+    }
+}
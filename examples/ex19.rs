@@ -0,0 +1,47 @@
+pub struct Api {
+    engine: Engine,
+}
+
+struct Engine {
+    cache: Cache,
+}
+
+type Cache = Vec<u8>;
+
+type Bytes = Vec<u8>;
+
+struct Scratch {
+    len: usize,
+}
+
+pub(crate) enum Leftover {
+    Empty,
+}
+
+struct List {
+    next: Option<Box<List>>,
+}
+
+pub struct Exported;
+
+trait Render {}
+
+impl Render for Api {}
+
+trait Orphan {}
+
+trait Bound {}
+
+struct Wrapper<T: Bound> {
+    inner: T,
+}
+
+pub trait Plugin {}
+
+pub mod api {
+    pub struct Handle;
+}
+
+mod internal {
+    pub struct Helper;
+}
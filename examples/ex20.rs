@@ -0,0 +1,35 @@
+pub struct Client {
+    transport: Transport,
+    #[cfg(feature = "json")]
+    codec: Json,
+    #[cfg(test)]
+    mock: Mock,
+}
+
+pub enum Transport {
+    #[cfg(unix)]
+    Unix(UnixSocket),
+    #[cfg(windows)]
+    Windows(WindowsPipe),
+    #[cfg(not(any(unix, windows)))]
+    Other,
+    Tcp(std::net::TcpStream),
+}
+
+#[cfg(feature = "json")]
+pub struct Json;
+
+#[cfg(unix)]
+pub struct UnixSocket {
+    fd: i32,
+}
+
+#[cfg(windows)]
+pub struct WindowsPipe {
+    handle: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    pub struct Mock;
+}
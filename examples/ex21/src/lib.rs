@@ -0,0 +1,16 @@
+mod net;
+mod store;
+#[cfg(not(unix))]
+#[path = "legacy.rs"]
+mod compat;
+mod missing;
+
+use net::{Client, Transport};
+use store::Store;
+
+pub struct App {
+    client: Client,
+    store: Store,
+}
+
+impl Transport for Client {}
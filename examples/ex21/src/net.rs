@@ -0,0 +1,9 @@
+mod proto;
+
+use proto::Frame;
+
+pub struct Client {
+    frames: Vec<Frame>,
+}
+
+pub trait Transport {}
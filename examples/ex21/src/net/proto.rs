@@ -0,0 +1,3 @@
+pub struct Frame {
+    len: u32,
+}
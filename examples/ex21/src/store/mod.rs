@@ -0,0 +1,7 @@
+mod row;
+
+use row::Row;
+
+pub struct Store {
+    rows: Vec<Row>,
+}
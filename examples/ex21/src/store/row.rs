@@ -0,0 +1,3 @@
+pub struct Row {
+    id: u64,
+}
@@ -0,0 +1,3 @@
+struct Tool {
+    name: String,
+}
@@ -0,0 +1,3 @@
+struct Cli {
+    verbose: bool,
+}
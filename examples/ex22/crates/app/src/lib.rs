@@ -0,0 +1,18 @@
+pub struct Engine {
+    cache: Cache,
+    #[cfg(feature = "fast")]
+    index: Index,
+}
+
+struct Cache {
+    entries: Vec<Entry>,
+}
+
+#[cfg(feature = "fast")]
+struct Index {
+    keys: Vec<String>,
+}
+
+struct Entry {
+    key: String,
+}
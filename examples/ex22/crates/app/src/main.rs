@@ -0,0 +1,3 @@
+struct Server {
+    port: u16,
+}
@@ -0,0 +1,8 @@
+struct Core {
+    state: State,
+}
+
+enum State {
+    Idle,
+    Busy(u32),
+}
@@ -0,0 +1,14 @@
+use super::{Render, Stage};
+
+pub struct Callback<F: Fn(Stage) -> Report> {
+    run: F,
+    filter: Option<fn(&Stage) -> bool>,
+}
+
+pub struct Report {
+    lines: Vec<String>,
+}
+
+pub struct Sink<I: Iterator<Item: Render>> {
+    items: I,
+}
@@ -0,0 +1,23 @@
+mod callback;
+
+use callback::{Callback, Report};
+
+pub struct Buffer<const N: usize> {
+    bytes: [u8; N],
+}
+
+pub struct View<'a, T: 'a + Render> {
+    items: &'a [T],
+    buffer: Buffer<16>,
+}
+
+pub trait Render {}
+
+pub struct Pipeline<I: Iterator<Item = Stage>> {
+    stages: I,
+    on_done: Callback<fn(Stage) -> Report>,
+}
+
+pub struct Stage {
+    name: String,
+}
@@ -0,0 +1,16 @@
+mod domain {
+    pub struct Order {
+        mailer: crate::infra::Mailer,
+        invoice: self::billing::Invoice,
+    }
+
+    pub mod billing {
+        pub struct Invoice {
+            mailer: super::super::infra::Mailer,
+        }
+    }
+}
+
+mod infra {
+    pub struct Mailer;
+}
@@ -0,0 +1,126 @@
+//! Baselines of known rule violations, so rules can be adopted on an existing
+//! codebase and tightened over time.
+//!
+//! A baseline is a TOML file listing violations by rule and subject:
+//!
+//! ```toml
+//! [[violation]]
+//! rule = "domain::* -> infra::*"
+//! subject = "domain::Order -> infra::Mailer"
+//! ```
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::rules::Violation;
+
+/// Known violations, sorted and without duplicates
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    #[serde(default, rename = "violation")]
+    pub violations: Vec<BaselineEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BaselineEntry {
+    pub rule: String,
+    pub subject: String,
+}
+
+/// How the current violations compare to a baseline
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Ratchet {
+    /// Violations that aren't in the baseline
+    pub new: Vec<Violation>,
+    /// Violations in the baseline that are still there
+    pub known: Vec<Violation>,
+    /// Entries of the baseline that no longer occur
+    pub fixed: Vec<BaselineEntry>,
+}
+
+impl From<&Violation> for BaselineEntry {
+    fn from(v: &Violation) -> Self {
+        Self {
+            rule: v.rule.clone(),
+            subject: v.subject.clone(),
+        }
+    }
+}
+
+impl Baseline {
+    /// A baseline accepting exactly `violations`
+    pub fn new(violations: &[Violation]) -> Self {
+        let mut violations = violations
+            .iter()
+            .map(BaselineEntry::from)
+            .collect::<Vec<_>>();
+        violations.sort();
+        violations.dedup();
+        Self { violations }
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("baselines are always serializable")
+    }
+
+    pub fn compare(&self, violations: &[Violation]) -> Ratchet {
+        let (known, new) = violations
+            .iter()
+            .cloned()
+            .partition(|v| self.violations.contains(&v.into()));
+        let fixed = self
+            .violations
+            .iter()
+            .filter(|e| !violations.iter().any(|v| BaselineEntry::from(v) == **e))
+            .cloned()
+            .collect();
+        Ratchet { new, known, fixed }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::TypeMap;
+
+    #[test]
+    fn test_ratchet() {
+        let tm = TypeMap::build("examples/ex18.rs").unwrap();
+        let rules = Rules::from_toml(
+            "[[forbid]]\nfrom = \"*\"\nto = \"sqlx::*\"\n[limits]\nmax_fan_out = 1\n",
+        )
+        .unwrap();
+        let violations = tm.check(&rules);
+        assert_eq!(violations.len(), 3);
+
+        let baseline = Baseline::new(&violations);
+        let toml = baseline.to_toml();
+        assert!(toml.starts_with(
+            "[[violation]]\nrule = \"* -> sqlx::*\"\nsubject = \"db::Store -> sqlx::PgPool\"\n"
+        ));
+        let baseline = Baseline::from_toml(&toml).unwrap();
+        let ratchet = baseline.compare(&violations);
+        assert!(ratchet.new.is_empty() && ratchet.fixed.is_empty());
+        assert_eq!(ratchet.known.len(), 3);
+
+        // Tightening a rule makes new violations; dropping one fixes some
+        let rules = Rules::from_toml("[[forbid]]\nfrom = \"*\"\nto = \"sqlx::*\"\n[[forbid]]\nfrom = \"domain::*\"\nto = \"infra::*\"\n").unwrap();
+        let ratchet = baseline.compare(&tm.check(&rules));
+        assert_eq!(ratchet.new.len(), 1);
+        assert_eq!(ratchet.new[0].subject, "domain::Order -> infra::Mailer");
+        assert_eq!(ratchet.known.len(), 2);
+        assert_eq!(
+            ratchet.fixed,
+            [BaselineEntry {
+                rule: "max_fan_out = 1".into(),
+                subject: "domain::Order".into(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! `cargo typemap`: run typemap on a target of the current crate, picked
+//! the way cargo picks targets to build.
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use typemap::cargo::{find_manifest, host_cfg, Package};
+use typemap::cli::{run, Args};
+use typemap::config::Config;
+
+#[derive(Parser)]
+#[clap(bin_name = "cargo")]
+enum Cargo {
+    Typemap(CargoArgs),
+}
+
+/// Visualize type dependence in a crate
+#[derive(clap::Args)]
+#[clap(version = "0.1")]
+struct CargoArgs {
+    /// Path to `Cargo.toml`. [default: the closest one to the current
+    /// directory]
+    #[clap(long, global = true)]
+    manifest_path: Option<PathBuf>,
+    /// Package of the workspace to analyze.
+    #[clap(short, long, global = true, value_name = "SPEC")]
+    package: Option<String>,
+    /// Analyze the library.
+    #[clap(long, global = true, conflicts_with = "bin")]
+    lib: bool,
+    /// Analyze the binary with this name.
+    #[clap(long, global = true, value_name = "NAME")]
+    bin: Option<String>,
+    /// Enable every feature of the package.
+    #[clap(long, global = true)]
+    all_features: bool,
+    /// Don't enable the `default` feature.
+    #[clap(long, global = true)]
+    no_default_features: bool,
+    #[clap(flatten)]
+    typemap: Args,
+}
+
+fn main() -> Result<()> {
+    let Cargo::Typemap(args) = Cargo::parse();
+    let manifest = match args.manifest_path {
+        Some(path) => path,
+        None => find_manifest(".".as_ref())?,
+    };
+    let package = Package::load(&manifest, args.package.as_deref())?;
+    let mut typemap = args.typemap;
+
+    // Settings come from the package, wherever typemap is run from
+    let config = match &typemap.config {
+        Some(path) => Config::load_required(path.as_ref())?,
+        None => match Config::discover(&package.dir)? {
+            Some((path, config)) => {
+                typemap.config = Some(path.to_string_lossy().into_owned());
+                config
+            }
+            None => Config::default(),
+        },
+    };
+
+    // The configured inputs are used unless a target is asked for
+    let explicit = args.lib || args.bin.is_some() || args.package.is_some();
+    if typemap.infile.is_empty() && (explicit || config.inputs.is_empty()) {
+        let target = package.select(args.lib, args.bin.as_deref())?;
+        typemap.infile = vec![target.path.to_string_lossy().into_owned()];
+    }
+
+    let requested = if typemap.features.is_empty() {
+        config.features
+    } else {
+        typemap.features
+    };
+    typemap.features = package.features(&requested, args.all_features, args.no_default_features)?;
+
+    // Evaluate `#[cfg]` as for a debug build on this host unless cfg options
+    // are given, so that code behind disabled features is left out without
+    // dropping platform code
+    if typemap.cfg.is_empty() {
+        typemap.cfg = if config.cfg.is_empty() {
+            host_cfg()
+        } else {
+            config.cfg
+        };
+    }
+    run(typemap)
+}
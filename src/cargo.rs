@@ -0,0 +1,444 @@
+//! Finding what to analyze from a `Cargo.toml`, for `cargo typemap`.
+//!
+//! Only the manifests are read, so this works offline and without building
+//! anything. Targets are found with cargo's defaults: `src/lib.rs`,
+//! `src/main.rs` and `src/bin/*.rs`, unless the manifest says otherwise.
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+
+use super::Set;
+use crate::input::normalize;
+
+/// A package, from its manifest
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub name: String,
+    /// Directory the manifest is in
+    pub dir: PathBuf,
+    manifest: toml::Table,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetKind {
+    Lib,
+    Bin,
+}
+
+/// A library or binary of a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub name: String,
+    pub kind: TargetKind,
+    /// The crate root
+    pub path: PathBuf,
+}
+
+/// The cfg options of a debug build for this host, like `unix` and
+/// `target_os = "linux"`, as cargo sets them without `--target`
+pub fn host_cfg() -> Vec<String> {
+    use std::env::consts::{ARCH, FAMILY, OS};
+    let endian = if cfg!(target_endian = "big") {
+        "big"
+    } else {
+        "little"
+    };
+    let env = if cfg!(target_env = "gnu") {
+        "gnu"
+    } else if cfg!(target_env = "musl") {
+        "musl"
+    } else if cfg!(target_env = "msvc") {
+        "msvc"
+    } else {
+        ""
+    };
+    let mut cfg = vec![
+        "debug_assertions".to_string(),
+        format!("target_os = {OS:?}"),
+        format!("target_family = {FAMILY:?}"),
+        format!("target_arch = {ARCH:?}"),
+        format!("target_pointer_width = \"{}\"", usize::BITS),
+        format!("target_endian = {endian:?}"),
+        format!("target_env = {env:?}"),
+    ];
+    // `unix` or `windows`
+    if !FAMILY.is_empty() {
+        cfg.push(FAMILY.into());
+    }
+    cfg
+}
+
+/// The closest `Cargo.toml` in `dir` or its ancestors
+pub fn find_manifest(dir: &Path) -> Result<PathBuf> {
+    // Walk up with `..` so paths in spans stay relative, as in `Config`
+    let mut dir = dir.to_path_buf();
+    for _ in std::fs::canonicalize(Path::new(".").join(&dir))?.ancestors() {
+        let path = normalize(&dir.join("Cargo.toml"));
+        if path.is_file() {
+            return Ok(path);
+        }
+        dir.push("..");
+    }
+    bail!("could not find `Cargo.toml` in this directory or any parent")
+}
+
+fn read_manifest(path: &Path) -> Result<toml::Table> {
+    let toml = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("could not read `{}`: {e}", path.display()))?;
+    toml.parse()
+        .map_err(|e| anyhow!("could not parse `{}`: {e}", path.display()))
+}
+
+/// `table.key` as a string
+fn str_at<'a>(table: &'a toml::Table, key: &str) -> Option<&'a str> {
+    table.get(key)?.as_str()
+}
+
+impl Package {
+    /// Load the package of `manifest`, or the member of its workspace named
+    /// `name` if one is given
+    pub fn load(manifest: &Path, name: Option<&str>) -> Result<Self> {
+        let table = read_manifest(manifest)?;
+        let dir = manifest.parent().unwrap_or(Path::new("")).to_path_buf();
+        let package = Self::new(dir.clone(), table.clone());
+        match (package, name) {
+            (Some(package), None) => return Ok(package),
+            (Some(package), Some(name)) if package.name == name => return Ok(package),
+            _ => {}
+        }
+
+        // Look for the package among the members of the workspace
+        let (root, workspace) = Self::workspace(&dir, table)?;
+        let members = Self::members(&root, &workspace)?;
+        let Some(name) = name else {
+            let names = members.iter().map(|m| m.name.as_str()).collect::<Vec<_>>();
+            bail!(
+                "`{}` is a virtual manifest, pick a package with -p: {}",
+                manifest.display(),
+                names.join(", ")
+            );
+        };
+        members
+            .into_iter()
+            .find(|m| m.name == name)
+            .ok_or_else(|| anyhow!("no package named `{name}` in the workspace"))
+    }
+
+    fn new(dir: PathBuf, manifest: toml::Table) -> Option<Self> {
+        let name = manifest.get("package")?.get("name")?.as_str()?.to_string();
+        Some(Self {
+            name,
+            dir,
+            manifest,
+        })
+    }
+
+    /// The `[workspace]` table of the manifest in `dir` or an ancestor, and
+    /// the directory it's in
+    fn workspace(dir: &Path, table: toml::Table) -> Result<(PathBuf, toml::Table)> {
+        let mut dir = dir.to_path_buf();
+        let mut table = Some(table);
+        for _ in std::fs::canonicalize(Path::new(".").join(&dir))?.ancestors() {
+            let manifest = match table.take() {
+                Some(table) => table,
+                None => {
+                    let path = normalize(&dir.join("Cargo.toml"));
+                    if !path.is_file() {
+                        dir.push("..");
+                        continue;
+                    }
+                    read_manifest(&path)?
+                }
+            };
+            if let Some(toml::Value::Table(workspace)) = manifest.get("workspace") {
+                return Ok((normalize(&dir), workspace.clone()));
+            }
+            dir.push("..");
+        }
+        bail!("not in a workspace")
+    }
+
+    /// Every member of a workspace, in the order of its `members` globs
+    fn members(root: &Path, workspace: &toml::Table) -> Result<Vec<Self>> {
+        let patterns = workspace
+            .get("members")
+            .and_then(|m| m.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|m| m.as_str());
+        let mut members = vec![];
+        for pattern in patterns {
+            let pattern = root.join(pattern);
+            let mut dirs = glob::glob(&pattern.to_string_lossy())?
+                .filter_map(|d| d.ok())
+                .collect::<Vec<_>>();
+            dirs.sort();
+            for dir in dirs {
+                let manifest = dir.join("Cargo.toml");
+                if let Some(member) = Self::new(dir, read_manifest(&manifest)?) {
+                    members.push(member);
+                }
+            }
+        }
+        Ok(members)
+    }
+
+    /// The library, if any, then the binaries sorted by name
+    pub fn targets(&self) -> Vec<Target> {
+        let src = self.dir.join("src");
+        let mut targets = vec![];
+
+        let lib = self.manifest.get("lib").and_then(|l| l.as_table());
+        let lib_path = lib
+            .and_then(|l| str_at(l, "path"))
+            .map(|p| self.dir.join(p));
+        if let Some(path) = lib_path.or_else(|| Some(src.join("lib.rs")).filter(|p| p.is_file())) {
+            let name = lib.and_then(|l| str_at(l, "name"));
+            targets.push(Target {
+                name: name.unwrap_or(&self.name).replace('-', "_"),
+                kind: TargetKind::Lib,
+                path,
+            });
+        }
+
+        let mut bins = vec![];
+        let explicit = self
+            .manifest
+            .get("bin")
+            .and_then(|b| b.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|b| b.as_table());
+        for bin in explicit {
+            let Some(name) = str_at(bin, "name") else {
+                continue;
+            };
+            let path = match str_at(bin, "path") {
+                Some(path) => self.dir.join(path),
+                None if name == self.name => src.join("main.rs"),
+                None => match src.join("bin").join(format!("{name}.rs")) {
+                    file if file.is_file() => file,
+                    _ => src.join("bin").join(name).join("main.rs"),
+                },
+            };
+            bins.push((name.to_string(), path));
+        }
+
+        let autobins = self.manifest["package"]
+            .get("autobins")
+            .and_then(|a| a.as_bool())
+            .unwrap_or(true);
+        if autobins {
+            let mut found = vec![];
+            if src.join("main.rs").is_file() {
+                found.push((self.name.clone(), src.join("main.rs")));
+            }
+            for entry in std::fs::read_dir(src.join("bin")).into_iter().flatten() {
+                let Ok(entry) = entry else { continue };
+                let path = entry.path();
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                if path.extension().is_some_and(|e| e == "rs") {
+                    found.push((name.into_owned(), path));
+                } else if path.join("main.rs").is_file() {
+                    found.push((name.into_owned(), path.join("main.rs")));
+                }
+            }
+            // Explicit targets take precedence over discovered ones
+            for (name, path) in found {
+                if !bins.iter().any(|(n, p)| *n == name || *p == path) {
+                    bins.push((name, path));
+                }
+            }
+        }
+
+        bins.sort();
+        targets.extend(bins.into_iter().map(|(name, path)| Target {
+            name,
+            kind: TargetKind::Bin,
+            path,
+        }));
+        targets
+    }
+
+    /// The target to analyze: the library with `lib`, the binary named `bin`,
+    /// or else the library, or the only binary
+    pub fn select(&self, lib: bool, bin: Option<&str>) -> Result<Target> {
+        let targets = self.targets();
+        let bins = || {
+            targets
+                .iter()
+                .filter(|t| t.kind == TargetKind::Bin)
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if let Some(bin) = bin {
+            return targets
+                .iter()
+                .find(|t| t.kind == TargetKind::Bin && t.name == bin)
+                .cloned()
+                .ok_or_else(|| match bins().as_str() {
+                    "" => anyhow!("package `{}` has no binaries", self.name),
+                    bins => anyhow!(
+                        "no binary `{bin}` in package `{}`, available: {bins}",
+                        self.name
+                    ),
+                });
+        }
+        if let Some(lib) = targets.iter().find(|t| t.kind == TargetKind::Lib) {
+            return Ok(lib.clone());
+        }
+        if lib {
+            bail!("package `{}` has no library", self.name);
+        }
+        match targets.as_slice() {
+            [] => bail!("package `{}` has no library or binaries", self.name),
+            [bin] => Ok(bin.clone()),
+            _ => bail!(
+                "package `{}` has several binaries, pick one with --bin: {}",
+                self.name,
+                bins()
+            ),
+        }
+    }
+
+    /// The features enabled by `requested`, and by the `default` feature
+    /// unless `no_default`, including the features they enable in turn.
+    /// `all` enables every feature.
+    pub fn features(
+        &self,
+        requested: &[String],
+        all: bool,
+        no_default: bool,
+    ) -> Result<Vec<String>> {
+        let table = self
+            .manifest
+            .get("features")
+            .and_then(|f| f.as_table())
+            .cloned()
+            .unwrap_or_default();
+        let entries = |name: &str| -> Vec<String> {
+            table
+                .get(name)
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        };
+
+        // Optional dependencies are features too, unless a `dep:` entry
+        // refers to them
+        let deps = self
+            .manifest
+            .get("dependencies")
+            .and_then(|d| d.as_table())
+            .into_iter()
+            .flatten()
+            .filter(|(_, d)| d.get("optional").and_then(|o| o.as_bool()) == Some(true))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        let explicit = table
+            .keys()
+            .flat_map(|k| entries(k))
+            .filter_map(|e| e.strip_prefix("dep:").map(String::from))
+            .collect::<Set<_>>();
+        let mut known = table.keys().cloned().collect::<Set<_>>();
+        known.extend(deps.into_iter().filter(|d| !explicit.contains(d)));
+
+        let mut queue = requested.to_vec();
+        if all {
+            queue.extend(known.iter().cloned());
+        }
+        if !no_default && table.contains_key("default") {
+            queue.push("default".into());
+        }
+        let mut enabled = Set::new();
+        while let Some(feature) = queue.pop() {
+            if !known.contains(&feature) {
+                bail!("package `{}` has no feature `{feature}`", self.name);
+            }
+            if !enabled.insert(feature.clone()) {
+                continue;
+            }
+            for entry in entries(&feature) {
+                // `dep:x` and `x?/y` don't enable features of this package,
+                // and `x/y` only enables `x`
+                if entry.starts_with("dep:") {
+                    continue;
+                }
+                match entry.split_once('/') {
+                    Some((dep, _)) if dep.ends_with('?') => {}
+                    Some((dep, _)) if known.contains(dep) => queue.push(dep.into()),
+                    Some(_) => {}
+                    None => queue.push(entry),
+                }
+            }
+        }
+        Ok(enabled.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_targets() {
+        let manifest = find_manifest(Path::new("examples/ex22/crates/app/src")).unwrap();
+        assert_eq!(manifest, Path::new("examples/ex22/crates/app/Cargo.toml"));
+        let app = Package::load(&manifest, None).unwrap();
+        let names = app
+            .targets()
+            .into_iter()
+            .map(|t| t.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["app", "app", "app-cli", "tool"]);
+        assert_eq!(app.select(false, None).unwrap().kind, TargetKind::Lib);
+        assert_eq!(
+            app.select(false, Some("app-cli")).unwrap().path,
+            Path::new("examples/ex22/crates/app/src/cli.rs")
+        );
+        assert!(app.select(false, Some("nope")).is_err());
+
+        // Binaries only
+        let root = Path::new("examples/ex22/Cargo.toml");
+        assert!(Package::load(root, None).is_err());
+        let core = Package::load(root, Some("ex22-core")).unwrap();
+        assert_eq!(core.dir, Path::new("examples/ex22/crates/core"));
+        assert_eq!(
+            core.select(false, None).unwrap().path,
+            Path::new("examples/ex22/crates/core/src/main.rs")
+        );
+        assert!(core.select(true, None).is_err());
+        assert!(Package::load(&manifest, Some("ex22-core")).is_ok());
+    }
+
+    #[test]
+    fn test_features() {
+        let manifest = Path::new("examples/ex22/crates/app/Cargo.toml");
+        let app = Package::load(manifest, None).unwrap();
+        assert_eq!(
+            app.features(&[], false, false).unwrap(),
+            ["default", "fast"]
+        );
+        assert!(app.features(&[], false, true).unwrap().is_empty());
+        assert_eq!(
+            app.features(&["full".into()], false, true).unwrap(),
+            ["fast", "full", "serde"]
+        );
+        assert_eq!(
+            app.features(&[], true, false).unwrap(),
+            ["default", "fast", "full", "log", "serde"]
+        );
+        assert!(app.features(&["nope".into()], false, false).is_err());
+    }
+
+    #[test]
+    fn test_host_cfg() {
+        let cfg = host_cfg();
+        assert!(cfg.contains(&"debug_assertions".to_string()));
+        assert!(cfg.contains(&format!("target_os = {:?}", std::env::consts::OS)));
+        assert!(crate::cfg::Cfg::enabled(&cfg).is_ok());
+    }
+}
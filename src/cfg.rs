@@ -0,0 +1,215 @@
+//! Conditional compilation: evaluating `#[cfg(...)]` attributes against a set
+//! of enabled options, like `test` or `feature = "serde"`.
+//!
+//! Nothing is enabled implicitly, so target options like `unix` must be given
+//! too if the analyzed code depends on them.
+use anyhow::{anyhow, Result};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, ExprLit, Fields, Item, ItemMod, Lit, Meta, Token};
+
+use super::{Set, TypeMap};
+use crate::input::Parsed;
+
+/// Which `#[cfg]`-gated code to analyze
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    /// Enabled options as (name, value). `None` ignores `#[cfg]` attributes
+    /// and analyzes all code, which is the default.
+    enabled: Option<Set<(String, Option<String>)>>,
+}
+
+impl Cfg {
+    /// Only analyze code enabled by `options`, each like `test` or
+    /// `feature = "serde"`
+    pub fn enabled<S: AsRef<str>>(options: impl IntoIterator<Item = S>) -> Result<Self> {
+        let enabled = options
+            .into_iter()
+            .map(|option| {
+                let option = option.as_ref();
+                match syn::parse_str::<Meta>(option) {
+                    Ok(meta) => name_value(&meta),
+                    Err(_) => None,
+                }
+                .ok_or_else(|| {
+                    anyhow!("invalid cfg option `{option}`, expected `name` or `name = \"value\"`")
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            enabled: Some(enabled),
+        })
+    }
+
+    /// Whether code with these attributes is compiled. Predicates that can't
+    /// be understood count as true, so nothing is dropped by mistake.
+    pub fn is_active(&self, attrs: &[Attribute]) -> bool {
+        let Some(enabled) = &self.enabled else {
+            return true;
+        };
+        attrs
+            .iter()
+            .filter(|a| a.path().is_ident("cfg"))
+            .all(|a| match a.parse_args::<Meta>() {
+                Ok(meta) => eval(&meta, enabled).unwrap_or(true),
+                Err(_) => true,
+            })
+    }
+
+    /// Remove inactive items, fields and variants, including those in inline
+    /// modules
+    pub fn strip(&self, items: &mut Vec<Item>) {
+        if self.enabled.is_none() {
+            return;
+        }
+        items.retain(|item| self.is_active(item_attrs(item)));
+        for item in items {
+            match item {
+                Item::Struct(s) => self.strip_fields(&mut s.fields),
+                Item::Enum(e) => {
+                    e.variants = std::mem::take(&mut e.variants)
+                        .into_iter()
+                        .filter(|v| self.is_active(&v.attrs))
+                        .collect();
+                    for v in &mut e.variants {
+                        self.strip_fields(&mut v.fields);
+                    }
+                }
+                Item::Union(u) => {
+                    u.fields.named = std::mem::take(&mut u.fields.named)
+                        .into_iter()
+                        .filter(|f| self.is_active(&f.attrs))
+                        .collect();
+                }
+                Item::Mod(ItemMod {
+                    content: Some((_, items)),
+                    ..
+                }) => self.strip(items),
+                _ => {}
+            }
+        }
+    }
+
+    fn strip_fields(&self, fields: &mut Fields) {
+        match fields {
+            Fields::Named(f) => {
+                f.named = std::mem::take(&mut f.named)
+                    .into_iter()
+                    .filter(|f| self.is_active(&f.attrs))
+                    .collect();
+            }
+            Fields::Unnamed(f) => {
+                f.unnamed = std::mem::take(&mut f.unnamed)
+                    .into_iter()
+                    .filter(|f| self.is_active(&f.attrs))
+                    .collect();
+            }
+            Fields::Unit => {}
+        }
+    }
+}
+
+impl TypeMap {
+    /// Build a `TypeMap` from Rust source code, leaving out code disabled by
+    /// `cfg`. `src` is the file name used in spans and diagnostics.
+    pub fn from_source_with(source: &str, src: &str, cfg: &Cfg) -> Result<Self> {
+        let mut ast = syn::parse_file(source)?;
+        cfg.strip(&mut ast.items);
+        Ok(Self::from_files(&[Parsed {
+            ast,
+            src: src.into(),
+            module: String::new(),
+        }]))
+    }
+}
+
+/// A `name` or `name = "value"` predicate as (name, value)
+fn name_value(meta: &Meta) -> Option<(String, Option<String>)> {
+    let name = meta.path().get_ident()?.to_string();
+    match meta {
+        Meta::Path(_) => Some((name, None)),
+        Meta::NameValue(nv) => match &nv.value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) => Some((name, Some(s.value()))),
+            _ => None,
+        },
+        Meta::List(_) => None,
+    }
+}
+
+/// Evaluate a cfg predicate, or `None` if it isn't valid
+fn eval(meta: &Meta, enabled: &Set<(String, Option<String>)>) -> Option<bool> {
+    let Meta::List(list) = meta else {
+        return Some(enabled.contains(&name_value(meta)?));
+    };
+    let args = list
+        .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+        .ok()?;
+    let values = args
+        .iter()
+        .map(|m| eval(m, enabled))
+        .collect::<Option<Vec<_>>>()?;
+    match list.path.get_ident()?.to_string().as_str() {
+        "all" => Some(values.iter().all(|v| *v)),
+        "any" => Some(values.iter().any(|v| *v)),
+        "not" if values.len() == 1 => Some(!values[0]),
+        _ => None,
+    }
+}
+
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Const(i) => &i.attrs,
+        Item::Enum(i) => &i.attrs,
+        Item::ExternCrate(i) => &i.attrs,
+        Item::Fn(i) => &i.attrs,
+        Item::ForeignMod(i) => &i.attrs,
+        Item::Impl(i) => &i.attrs,
+        Item::Macro(i) => &i.attrs,
+        Item::Mod(i) => &i.attrs,
+        Item::Static(i) => &i.attrs,
+        Item::Struct(i) => &i.attrs,
+        Item::Trait(i) => &i.attrs,
+        Item::TraitAlias(i) => &i.attrs,
+        Item::Type(i) => &i.attrs,
+        Item::Union(i) => &i.attrs,
+        Item::Use(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build(cfg: &Cfg) -> TypeMap {
+        let source = std::fs::read_to_string("examples/ex20.rs").unwrap();
+        TypeMap::from_source_with(&source, "examples/ex20.rs", cfg).unwrap()
+    }
+
+    #[test]
+    fn test_cfg() {
+        let all = build(&Cfg::default());
+        assert!(all.info("Mock").is_some());
+        assert_eq!(all.edge_causes("Client", "Json"), ["codec"]);
+
+        let cfg = Cfg::enabled(["unix", "feature = \"json\""]).unwrap();
+        let tm = build(&cfg);
+        assert!(tm.info("Mock").is_none());
+        assert!(tm.info("UnixSocket").is_some());
+        assert!(tm.info("WindowsPipe").is_none());
+        assert_eq!(tm.edge_causes("Client", "Json"), ["codec"]);
+        assert!(tm.edge_causes("Client", "Mock").is_empty());
+        let members = &tm.info("Transport").unwrap().members;
+        assert_eq!(members.len(), 2);
+
+        let tm = build(&Cfg::enabled(["test", "windows"]).unwrap());
+        assert!(tm.info("Mock").is_some());
+        assert!(tm.info("UnixSocket").is_none());
+        assert!(tm.edge_causes("Client", "Json").is_empty());
+        assert_eq!(tm.info("Transport").unwrap().members.len(), 2);
+
+        assert!(Cfg::enabled(["all(unix)"]).is_err());
+        assert!(Cfg::enabled(["feature = 1"]).is_err());
+    }
+}
@@ -0,0 +1,721 @@
+//! The `typemap` command line, shared by the `typemap` and `cargo typemap`
+//! binaries.
+use std::io::Write;
+
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::baseline::Baseline;
+use crate::cfg::Cfg;
+use crate::config::Config;
+use crate::cycles::highlight_cycles;
+use crate::diff::TypeMapDiff;
+use crate::dot::{dot_graph, render, DotOptions, Highlight, RenderFormat};
+use crate::git::read_at_rev;
+use crate::graphml::generate_graphml;
+use crate::html::generate_html;
+use crate::input::Cache;
+use crate::mermaid::generate_mermaid;
+use crate::plantuml::generate_plantuml;
+use crate::query::path_to_string;
+use crate::rules::Rules;
+use crate::theme::Theme;
+use crate::watch::{watch, Server};
+use crate::TypeMap;
+
+#[derive(Parser)]
+#[clap(
+    about = "Visualize type dependence in your Rust projects",
+    version = "0.1"
+)]
+pub struct Args {
+    /// Rust file, directory or quoted glob to analyze, `-` for stdin, or a
+    /// typemap saved with `typemap export`. Can be repeated to merge several
+    /// into one graph. Required by everything but `diff`.
+    #[clap(short, long, global = true)]
+    pub infile: Vec<String>,
+    /// Analyze `--infile` as of this git revision, like `HEAD~3` or `main`,
+    /// without checking it out.
+    #[clap(long, global = true)]
+    pub rev: Option<String>,
+    /// Only analyze code enabled by this cfg option, like `test` or
+    /// `feature="serde"`. Can be repeated. Without it, `#[cfg]` attributes
+    /// are ignored.
+    #[clap(long, global = true, value_name = "OPTION")]
+    pub cfg: Vec<String>,
+    /// Enable these features, like `--cfg feature="NAME"` for each.
+    /// Comma-separated or repeated.
+    #[clap(long, global = true, value_delimiter = ',')]
+    pub features: Vec<String>,
+    /// Only keep the transitive dependencies of this type.
+    #[clap(long, global = true, conflicts_with = "reverse")]
+    pub root: Option<String>,
+    /// Only keep the transitive dependents of this type.
+    #[clap(long, global = true)]
+    pub reverse: Option<String>,
+    /// Maximum number of hops to follow from `--root` or `--reverse`.
+    #[clap(long, global = true)]
+    pub depth: Option<usize>,
+    /// Leave out types matching this pattern, like `String` or `std::*`.
+    /// Can be repeated.
+    #[clap(long, global = true, value_name = "PATTERN")]
+    pub hide: Vec<String>,
+    /// Configuration file to use instead of the `typemap.toml` or
+    /// `Cargo.toml` found in the current directory or its ancestors.
+    #[clap(long, global = true)]
+    pub config: Option<String>,
+    /// Run again whenever the analyzed files change, until interrupted.
+    /// Only the files that changed are parsed again.
+    #[clap(long, global = true)]
+    pub watch: bool,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Draw the graph with graphviz, or as an interactive HTML page.
+    Render {
+        /// Serve the HTML viewer on this address instead, reloading it in
+        /// the browser whenever the files change. Implies `--watch`.
+        #[clap(
+            long,
+            value_name = "ADDR",
+            num_args = 0..=1,
+            default_missing_value = "127.0.0.1:8000",
+            conflicts_with_all = ["outfile", "format"]
+        )]
+        serve: Option<String>,
+        #[clap(flatten)]
+        render: RenderArgs,
+    },
+    /// Write the graph in a format for other tools.
+    Export {
+        /// File to output to. If none, will print to stdout.
+        #[clap(short, long)]
+        outfile: Option<String>,
+        /// Output format. Inferred from the `--outfile` extension if not
+        /// given, falling back to json.
+        #[clap(short, long, value_enum)]
+        format: Option<ExportFormat>,
+    },
+    /// List the types in the graph, after `--root` or `--reverse`.
+    Query,
+    /// Explain why one type depends on another.
+    Paths {
+        /// Type the paths start from.
+        from: String,
+        /// Type the paths end at.
+        to: String,
+        /// List every simple path instead of just the shortest one.
+        #[clap(long)]
+        all: bool,
+        /// Longest path (in hops) to consider with `--all`.
+        #[clap(long, default_value_t = 8)]
+        max_hops: usize,
+        /// Render the graph with the paths highlighted instead of listing them.
+        #[clap(long)]
+        highlight: bool,
+        #[clap(flatten)]
+        render: RenderArgs,
+    },
+    /// List types in dependency order, grouped by level.
+    Order,
+    /// Find groups of types that depend on each other.
+    Cycles {
+        /// Render the graph with each cycle colored instead of listing them.
+        #[clap(long)]
+        highlight: bool,
+        #[clap(flatten)]
+        render: RenderArgs,
+    },
+    /// List private types, traits and aliases that no other type refers to.
+    Unused,
+    /// Report coupling metrics for each type and module.
+    Metrics {
+        #[clap(long, value_enum, default_value_t = MetricsFormat::Table)]
+        format: MetricsFormat,
+    },
+    /// Compare two versions of a codebase.
+    Diff {
+        /// Rust file or saved typemap of the old version.
+        old: String,
+        /// Rust file or saved typemap of the new version. Defaults to `OLD`,
+        /// for comparing revisions of the same file.
+        new: Option<String>,
+        /// Read `OLD` as of this git revision.
+        #[clap(long)]
+        old_rev: Option<String>,
+        /// Read `NEW` as of this git revision.
+        #[clap(long)]
+        new_rev: Option<String>,
+        /// Render both versions with additions in green and removals in red
+        /// instead of listing the changes.
+        #[clap(long)]
+        highlight: bool,
+        #[clap(flatten)]
+        render: RenderArgs,
+    },
+    /// Check architecture rules, exiting with an error if any are broken.
+    Check {
+        /// TOML file with the rules. See `src/rules.rs` for the format. Can
+        /// be repeated to merge several. [default: typemap-rules.toml]
+        #[clap(long)]
+        rules: Vec<String>,
+        /// Only fail on violations missing from this baseline of known ones,
+        /// and report the ones that have been fixed.
+        #[clap(long)]
+        baseline: Option<String>,
+        /// Write the current violations to the `--baseline` file instead.
+        #[clap(long)]
+        update_baseline: bool,
+    },
+}
+
+/// Options for drawing the graph
+#[derive(clap::Args)]
+struct RenderArgs {
+    /// File to output to. If none, will print to stdout.
+    #[clap(short, long)]
+    outfile: Option<String>,
+    /// Output format. Inferred from the `--outfile` extension if not given,
+    /// falling back to pdf for files and dot for stdout.
+    #[clap(short, long, value_enum)]
+    format: Option<Format>,
+    /// Group the types of each module in a box.
+    #[clap(long, overrides_with = "no_clusters")]
+    clusters: bool,
+    /// Don't group types by module, even if the configuration does.
+    #[clap(long, overrides_with = "clusters")]
+    no_clusters: bool,
+    /// Draw a module and its submodules as a single node. Can be repeated.
+    #[clap(long, value_name = "MODULE")]
+    collapse: Vec<String>,
+    /// Draw types as tables of their fields and variants, UML style.
+    #[clap(long, overrides_with = "no_records")]
+    records: bool,
+    /// Draw types as plain boxes, even if the configuration uses records.
+    #[clap(long, overrides_with = "records")]
+    no_records: bool,
+    /// Color theme: `light`, `dark`, or a TOML theme file. [default: light]
+    #[clap(long)]
+    theme: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Dot,
+    Svg,
+    Png,
+    Pdf,
+    Html,
+}
+
+impl Format {
+    fn from_path(path: &str) -> Option<Self> {
+        if let Some(format) = RenderFormat::from_path(path) {
+            return Some(match format {
+                RenderFormat::Dot => Self::Dot,
+                RenderFormat::Svg => Self::Svg,
+                RenderFormat::Png => Self::Png,
+                RenderFormat::Pdf => Self::Pdf,
+            });
+        }
+        match std::path::Path::new(path).extension()?.to_str()? {
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// The DOT-based format this corresponds to, if any
+    fn render_format(self) -> Option<RenderFormat> {
+        match self {
+            Self::Dot => Some(RenderFormat::Dot),
+            Self::Svg => Some(RenderFormat::Svg),
+            Self::Png => Some(RenderFormat::Png),
+            Self::Pdf => Some(RenderFormat::Pdf),
+            Self::Html => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Mermaid,
+    Plantuml,
+    Graphml,
+}
+
+impl ExportFormat {
+    fn from_path(path: &str) -> Option<Self> {
+        Some(match std::path::Path::new(path).extension()?.to_str()? {
+            "json" => Self::Json,
+            "mmd" | "mermaid" => Self::Mermaid,
+            "puml" | "plantuml" => Self::Plantuml,
+            "graphml" => Self::Graphml,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MetricsFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+impl RenderArgs {
+    fn theme(&self) -> Result<Theme> {
+        let name = self.theme.as_deref().unwrap_or("light");
+        match Theme::preset(name) {
+            Some(theme) => Ok(theme),
+            None => Theme::from_toml(&std::fs::read_to_string(name)?),
+        }
+    }
+
+    /// Draw `typemap` with extra colors, if any, and write it out
+    fn write(&self, typemap: &TypeMap, highlight: Option<Highlight>) -> Result<()> {
+        let outfile = self.outfile.as_deref();
+        let format = self
+            .format
+            .or_else(|| outfile.and_then(Format::from_path))
+            .unwrap_or(match outfile {
+                Some(_) => Format::Pdf,
+                None => Format::Dot,
+            });
+        let theme = self.theme()?;
+
+        let Some(render_format) = format.render_format() else {
+            if highlight.is_some() {
+                bail!("highlighted graphs can only be written as dot, svg, png or pdf");
+            }
+            return write_output(outfile, generate_html(typemap, &theme).as_bytes());
+        };
+        let options = DotOptions {
+            highlight: highlight.unwrap_or_default(),
+            clusters: self.clusters,
+            collapse: self.collapse.clone(),
+            records: self.records,
+            theme,
+        };
+        let graph = dot_graph(typemap, &options);
+        write_output(outfile, &render(&graph, render_format)?)
+    }
+}
+
+impl Args {
+    /// Fill in options not given on the command line from `config`, and
+    /// check that the result is consistent, as clap only checks the command
+    /// line
+    fn with_config(mut self, config: Config) -> Result<Self> {
+        for (flag, value) in [
+            (&mut self.infile, config.inputs),
+            (&mut self.cfg, config.cfg),
+            (&mut self.features, config.features),
+            (&mut self.hide, config.hide),
+        ] {
+            if flag.is_empty() {
+                *flag = value;
+            }
+        }
+        if self.root.is_none() && self.reverse.is_none() {
+            self.root = config.root;
+            self.reverse = config.reverse;
+        }
+        if self.root.is_some() && self.reverse.is_some() {
+            bail!("`root` and `reverse` can't be used together");
+        }
+        self.depth = self.depth.or(config.depth);
+
+        let render = match &mut self.command {
+            Command::Render { render, .. }
+            | Command::Paths { render, .. }
+            | Command::Cycles { render, .. }
+            | Command::Diff { render, .. } => render,
+            Command::Check {
+                rules, baseline, ..
+            } => {
+                if rules.is_empty() {
+                    *rules = config.rules;
+                }
+                *baseline = baseline.take().or(config.baseline);
+                return Ok(self);
+            }
+            _ => return Ok(self),
+        };
+        render.theme = render.theme.take().or(config.theme);
+        // `--no-clusters` and `--no-records` turn off what the config enables
+        if !render.no_clusters {
+            render.clusters |= config.clusters;
+        }
+        if !render.no_records {
+            render.records |= config.records;
+        }
+        if render.collapse.is_empty() {
+            render.collapse = config.collapse;
+        }
+        Ok(self)
+    }
+}
+
+/// Run a parsed command line
+pub fn run(args: Args) -> Result<()> {
+    let config = match &args.config {
+        Some(path) => Config::load_required(path.as_ref())?,
+        None => Config::discover(".".as_ref())?
+            .map(|(_, config)| config)
+            .unwrap_or_default(),
+    };
+    let args = args.with_config(config)?;
+    let features = args.features.iter().map(|f| format!("feature = {f:?}"));
+    let options = args.cfg.iter().cloned().chain(features).collect::<Vec<_>>();
+    let cfg = if options.is_empty() {
+        Cfg::default()
+    } else {
+        Cfg::enabled(&options)?
+    };
+
+    let serve = match &args.command {
+        Command::Render { serve, .. } => serve.as_deref(),
+        _ => None,
+    };
+    if !args.watch && serve.is_none() {
+        return execute(&args, &cfg, &mut Cache::default(), None);
+    }
+    if args.rev.is_some() || args.infile.iter().any(|i| i == "-") {
+        bail!("only files on disk can be watched, not --rev or stdin");
+    }
+    let server = serve.map(Server::start).transpose()?;
+    if let Some(server) = &server {
+        eprintln!("serving the viewer on http://{}", server.addr);
+    }
+    watch(&mut Cache::default(), |cache| {
+        execute(&args, &cfg, cache, server.as_ref())
+    })
+}
+
+/// Run the command once, parsing files with `cache`, and sending HTML to
+/// `server` if there is one
+/// Apply the `--root`, `--reverse` and `--hide` options
+fn filter(mut typemap: TypeMap, args: &Args) -> Result<TypeMap> {
+    if let Some(root) = &args.root {
+        typemap = typemap.subgraph(&typemap.dependencies(root, args.depth)?);
+    } else if let Some(root) = &args.reverse {
+        typemap = typemap.subgraph(&typemap.dependents(root, args.depth)?);
+    }
+    if !args.hide.is_empty() {
+        typemap = typemap.hide(&args.hide);
+    }
+    Ok(typemap)
+}
+
+/// Both versions in one graph, filtered by `args`, and the changes between
+/// them. Both versions are filtered to the types kept in the graph, so that
+/// the listed changes match the drawing.
+fn filtered_diff(old: &TypeMap, new: &TypeMap, args: &Args) -> Result<(TypeMap, TypeMapDiff)> {
+    let union = filter(old.union(new), args)?;
+    let names = union.nodes();
+    let diff = old.subgraph(&names).diff(&new.subgraph(&names));
+    Ok((union, diff))
+}
+
+fn execute(args: &Args, cfg: &Cfg, cache: &mut Cache, server: Option<&Server>) -> Result<()> {
+    // `diff` reads its own inputs, and draws both versions at once
+    let mut diff = None;
+    let typemap = match (&args.command, &args.infile) {
+        (
+            Command::Diff {
+                old,
+                new,
+                old_rev,
+                new_rev,
+                ..
+            },
+            _,
+        ) => {
+            let new = load(
+                std::slice::from_ref(new.as_ref().unwrap_or(old)),
+                new_rev.as_deref(),
+                cfg,
+                cache,
+            )?;
+            let old = load(std::slice::from_ref(old), old_rev.as_deref(), cfg, cache)?;
+            let (union, changes) = filtered_diff(&old, &new, args)?;
+            diff = Some(changes);
+            union
+        }
+        (_, infiles) if !infiles.is_empty() => {
+            filter(load(infiles, args.rev.as_deref(), cfg, cache)?, args)?
+        }
+        _ => bail!("--infile is required"),
+    };
+
+    match &args.command {
+        Command::Render { render, .. } => match server {
+            Some(server) => {
+                server.update(&generate_html(&typemap, &render.theme()?));
+                Ok(())
+            }
+            None => render.write(&typemap, None),
+        },
+        Command::Export { outfile, format } => {
+            let outfile = outfile.as_deref();
+            let format = format
+                .or_else(|| outfile.and_then(ExportFormat::from_path))
+                .unwrap_or(ExportFormat::Json);
+            let contents = match format {
+                ExportFormat::Json => typemap.to_json(),
+                ExportFormat::Mermaid => generate_mermaid(&typemap),
+                ExportFormat::Plantuml => generate_plantuml(&typemap),
+                ExportFormat::Graphml => generate_graphml(&typemap),
+            };
+            write_output(outfile, contents.as_bytes())
+        }
+        Command::Query => {
+            let mut paths = typemap
+                .nodes()
+                .into_iter()
+                .map(|name| match typemap.info(&name) {
+                    Some(info) if !info.module.is_empty() => format!("{}::{name}", info.module),
+                    _ => name,
+                })
+                .collect::<Vec<_>>();
+            paths.sort();
+            for path in paths {
+                println!("{path}");
+            }
+            Ok(())
+        }
+        Command::Paths {
+            from,
+            to,
+            all,
+            max_hops,
+            highlight,
+            render,
+        } => {
+            let paths = if *all {
+                typemap.all_paths(from, to, *max_hops)?
+            } else {
+                typemap.shortest_path(from, to)?.into_iter().collect()
+            };
+            if !highlight {
+                if paths.is_empty() {
+                    println!("no path from `{from}` to `{to}`");
+                }
+                for path in &paths {
+                    println!("{}", path_to_string(path));
+                }
+                return Ok(());
+            }
+
+            let mut hl = Highlight::default();
+            for hop in paths.iter().flatten() {
+                hl.nodes.insert(hop.from.clone(), "red".into());
+                hl.nodes.insert(hop.to.clone(), "red".into());
+                hl.edges
+                    .insert((hop.from.clone(), hop.to.clone()), "red".into());
+            }
+            render.write(&typemap, Some(hl))
+        }
+        Command::Order => {
+            let cond = typemap.condensation();
+            for (level, types) in cond.levels.iter().zip(&cond.components) {
+                if types.len() > 1 {
+                    println!("{level}: {{{}}}", types.join(", "));
+                } else {
+                    println!("{level}: {}", types[0]);
+                }
+            }
+            Ok(())
+        }
+        Command::Cycles { highlight, render } => {
+            let cycles = typemap.cycles();
+            if !highlight {
+                for cycle in &cycles {
+                    let types = cycle.types.join(", ");
+                    if cycle.infinite {
+                        println!("{types} (infinitely sized, needs indirection like `Box`)");
+                    } else {
+                        println!("{types}");
+                    }
+                }
+                return Ok(());
+            }
+            render.write(&typemap, Some(highlight_cycles(&typemap, &cycles)))
+        }
+        Command::Unused => {
+            for unused in typemap.unused() {
+                println!("{unused}");
+            }
+            Ok(())
+        }
+        Command::Metrics { format } => {
+            let report = typemap.metrics();
+            match format {
+                MetricsFormat::Table => print!("{}", report.to_table()),
+                MetricsFormat::Csv => print!("{}", report.to_csv()),
+                MetricsFormat::Json => println!("{}", report.to_json()),
+            }
+            Ok(())
+        }
+        Command::Diff {
+            highlight, render, ..
+        } => {
+            let diff = diff.expect("diff is computed while loading");
+            if !highlight {
+                if diff.is_empty() {
+                    println!("no structural changes");
+                }
+                print!("{}", diff.to_text());
+                return Ok(());
+            }
+            render.write(&typemap, Some(diff.highlight()))
+        }
+        Command::Check {
+            rules,
+            baseline,
+            update_baseline,
+        } => {
+            if *update_baseline && baseline.is_none() {
+                bail!("--update-baseline needs a --baseline file to write");
+            }
+            let default = vec!["typemap-rules.toml".to_string()];
+            let mut all = Rules::default();
+            for path in if rules.is_empty() { &default } else { rules } {
+                let toml = std::fs::read_to_string(path)
+                    .map_err(|e| anyhow!("could not read rules from `{path}`: {e}"))?;
+                all.extend(Rules::from_toml(&toml)?);
+            }
+            let mut violations = typemap.check(&all);
+            if let Some(path) = baseline {
+                if *update_baseline {
+                    std::fs::write(path, Baseline::new(&violations).to_toml())?;
+                    eprintln!("wrote {} violation(s) to {path}", violations.len());
+                    return Ok(());
+                }
+                let baseline = Baseline::from_toml(&std::fs::read_to_string(path)?)?;
+                let ratchet = baseline.compare(&violations);
+                for entry in &ratchet.fixed {
+                    println!(
+                        "note: fixed `{}` (rule `{}`), remove it from {path}",
+                        entry.subject, entry.rule
+                    );
+                }
+                if !ratchet.known.is_empty() {
+                    eprintln!("{} known violation(s) in {path}", ratchet.known.len());
+                }
+                violations = ratchet.new;
+            }
+            for violation in &violations {
+                println!("error: {violation}");
+            }
+            // An error rather than exiting, so the binaries pick the exit
+            // code and watch mode carries on
+            if !violations.is_empty() {
+                bail!("{} rule violation(s)", violations.len());
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Analyze Rust sources, or load a typemap saved with `typemap export`, as of
+/// a git revision if one is given, and report what the analysis skipped.
+/// Files on disk are parsed with `cache`, and recorded there to be watched.
+fn load(inputs: &[String], rev: Option<&str>, cfg: &Cfg, cache: &mut Cache) -> Result<TypeMap> {
+    let typemap = match inputs {
+        [path] if path.ends_with(".json") => TypeMap::from_json(&match rev {
+            Some(rev) => read_at_rev(path, rev)?,
+            None => {
+                cache.watch(path.as_ref());
+                std::fs::read_to_string(path)?
+            }
+        })?,
+        _ if inputs.iter().any(|i| i.ends_with(".json")) => {
+            bail!("a saved typemap can't be merged with other inputs")
+        }
+        _ => TypeMap::build_cached(inputs, rev, cfg, cache)?,
+    };
+    for diag in typemap.diagnostics() {
+        match &diag.span {
+            Some(span) => eprintln!("warning: {span}: {}", diag.message),
+            None => eprintln!("warning: {}", diag.message),
+        }
+    }
+    Ok(typemap)
+}
+
+/// Write to `outfile`, or to stdout if there is none
+fn write_output(outfile: Option<&str>, contents: &[u8]) -> Result<()> {
+    match outfile {
+        Some(outfile) => std::fs::write(outfile, contents)?,
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(contents)?;
+            if !contents.ends_with(b"\n") {
+                stdout.write_all(b"\n")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn render_args(args: Args) -> RenderArgs {
+        match args.command {
+            Command::Render { render, .. } => render,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_with_config() {
+        let config = Config::from_toml("clusters = true\nrecords = true").unwrap();
+        let parse = |args: &[&str]| Args::try_parse_from(args).unwrap();
+
+        let render = render_args(
+            parse(&["typemap", "render"])
+                .with_config(config.clone())
+                .unwrap(),
+        );
+        assert!(render.clusters && render.records);
+        let args = parse(&["typemap", "render", "--no-clusters"]);
+        let render = render_args(args.with_config(config.clone()).unwrap());
+        assert!(!render.clusters && render.records);
+        let args = parse(&["typemap", "render", "--no-records", "--records"]);
+        assert!(render_args(args.with_config(config).unwrap()).records);
+
+        let config = Config::from_toml("root = \"A\"\nreverse = \"B\"").unwrap();
+        assert!(parse(&["typemap", "query"])
+            .with_config(config.clone())
+            .is_err());
+        let args = parse(&["typemap", "query", "--reverse", "C"])
+            .with_config(config)
+            .unwrap();
+        assert_eq!((args.root, args.reverse.as_deref()), (None, Some("C")));
+    }
+
+    #[test]
+    fn test_filtered_diff() {
+        let old = TypeMap::build("examples/ex16.rs").unwrap();
+        let new = TypeMap::build("examples/ex17.rs").unwrap();
+        let parse = |args: &[&str]| Args::try_parse_from(args).unwrap();
+
+        let (_, diff) = filtered_diff(&old, &new, &parse(&["typemap", "query"])).unwrap();
+        assert_eq!(diff.removed_types, ["Session"]);
+        assert_eq!(diff.added_types, ["Cache", "Role"]);
+
+        // Types left out of the graph aren't listed as changed
+        let args = parse(&["typemap", "query", "--hide", "Session", "--root", "User"]);
+        let (union, diff) = filtered_diff(&old, &new, &args).unwrap();
+        assert!(union.info("Session").is_none());
+        assert!(diff.removed_types.is_empty());
+        assert_eq!(diff.added_types, ["Role"]);
+    }
+}
@@ -0,0 +1,159 @@
+//! Project configuration, so every invocation renders the same views.
+//!
+//! Settings are read from `typemap.toml`, or from the
+//! `[package.metadata.typemap]` table of `Cargo.toml`, in the current
+//! directory or the closest ancestor that has either:
+//!
+//! ```toml
+//! inputs = ["src/lib.rs"]
+//! hide = ["String", "std::*"]
+//! features = ["serde"]
+//! theme = "dark"
+//! clusters = true
+//! rules = ["typemap-rules.toml"]
+//! baseline = "typemap-baseline.toml"
+//! ```
+//!
+//! Every key matches a command line flag, which replaces it when given.
+//! Relative paths are relative to the file the configuration is in.
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::input::normalize;
+use crate::theme::Theme;
+
+/// File names searched for, in order of preference
+const FILES: [&str; 2] = ["typemap.toml", "Cargo.toml"];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Files, directories and globs to analyze, as for `--infile`
+    pub inputs: Vec<String>,
+    /// Only keep the transitive dependencies of this type
+    pub root: Option<String>,
+    /// Only keep the transitive dependents of this type
+    pub reverse: Option<String>,
+    /// Maximum number of hops to follow from `root` or `reverse`
+    pub depth: Option<usize>,
+    /// Patterns of types to leave out, like `String` or `std::*`
+    pub hide: Vec<String>,
+    /// cfg options, like `test` or `feature = "serde"`
+    pub cfg: Vec<String>,
+    /// Features to enable, as for `--features`
+    pub features: Vec<String>,
+    /// `light`, `dark`, or a TOML theme file
+    pub theme: Option<String>,
+    pub clusters: bool,
+    pub collapse: Vec<String>,
+    pub records: bool,
+    /// Rule files for `typemap check`, merged in order
+    pub rules: Vec<String>,
+    /// Baseline of known violations for `typemap check`
+    pub baseline: Option<String>,
+}
+
+impl Config {
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Read a configuration file: `typemap.toml`, or a `Cargo.toml` whose
+    /// `[package.metadata.typemap]` table is used. `None` if a `Cargo.toml`
+    /// has no such table.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let context = |e: &dyn std::fmt::Display| anyhow!("{}: {e}", path.display());
+        let toml = std::fs::read_to_string(path).map_err(|e| context(&e))?;
+        let config = if path.file_name().is_some_and(|n| n == "Cargo.toml") {
+            let manifest: toml::Table = toml.parse().map_err(|e| context(&e))?;
+            let table = manifest
+                .get("package")
+                .and_then(|p| p.get("metadata"))
+                .and_then(|m| m.get("typemap"));
+            match table {
+                Some(table) => table.clone().try_into().map_err(|e| context(&e))?,
+                None => return Ok(None),
+            }
+        } else {
+            Self::from_toml(&toml).map_err(|e| context(&e))?
+        };
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Ok(Some(config.relative_to(dir)))
+    }
+
+    /// Read a configuration file given explicitly, which must have settings
+    pub fn load_required(path: &Path) -> Result<Self> {
+        Self::load(path)?.ok_or_else(|| {
+            anyhow!(
+                "`{}` has no [package.metadata.typemap] table",
+                path.display()
+            )
+        })
+    }
+
+    /// Find and load the configuration for `dir`, along with its path
+    pub fn discover(dir: &Path) -> Result<Option<(PathBuf, Self)>> {
+        // Walk up with `..` rather than through absolute paths, so that
+        // paths in spans stay relative. `dir` is empty for the current
+        // directory when it comes from a path like `Cargo.toml`.
+        let mut dir = dir.to_path_buf();
+        for _ in std::fs::canonicalize(Path::new(".").join(&dir))?.ancestors() {
+            for file in FILES {
+                let path = normalize(&dir.join(file));
+                if !path.is_file() {
+                    continue;
+                }
+                if let Some(config) = Self::load(&path)? {
+                    return Ok(Some((path, config)));
+                }
+            }
+            dir.push("..");
+        }
+        Ok(None)
+    }
+
+    /// Make relative paths relative to `dir` instead
+    fn relative_to(mut self, dir: &Path) -> Self {
+        let join = |path: &String| match path.as_str() {
+            "-" => path.clone(),
+            _ => dir.join(path).to_string_lossy().into_owned(),
+        };
+        self.inputs = self.inputs.iter().map(join).collect();
+        self.rules = self.rules.iter().map(join).collect();
+        self.baseline = self.baseline.as_ref().map(join);
+        self.theme = self.theme.map(|t| {
+            if Theme::preset(&t).is_some() {
+                t
+            } else {
+                join(&t)
+            }
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let (path, config) = Config::discover(Path::new("examples/ex21/src/net"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(path, Path::new("examples/ex21/Cargo.toml"));
+        assert_eq!(config.inputs, ["examples/ex21/src/lib.rs"]);
+        assert_eq!(config.hide, ["u*"]);
+        assert_eq!(config.theme.as_deref(), Some("dark"));
+        assert_eq!(config.rules, ["examples/ex21/rules.toml"]);
+        assert_eq!(config.cfg, ["unix"]);
+        assert_eq!(config.features, ["json"]);
+
+        let config = Config::from_toml("depth = 2\nclusters = true").unwrap();
+        assert_eq!(config.depth, Some(2));
+        assert!(config.clusters);
+        assert!(Config::from_toml("infile = \"a.rs\"").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Cycle and strongly connected component detection.
+use std::collections::{BTreeMap, HashMap};
+
+use super::{Set, TypeMap};
+use crate::dot::Highlight;
+use crate::query::Direction;
+
+/// Colors for cycles that are broken by indirection. Infinitely sized cycles
+/// are always drawn in red.
+const PALETTE: [&str; 6] = ["blue", "darkgreen", "purple", "darkorange", "brown", "teal"];
+
+/// A group of types that (transitively) depend on each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// Types in the cycle, sorted by name
+    pub types: Vec<String>,
+    /// Whether some of the types contain each other without any indirection,
+    /// making them infinitely sized (rustc error E0072)
+    pub infinite: bool,
+}
+
+/// The dependency graph with each strongly connected component collapsed into
+/// a single node, which makes it acyclic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condensation {
+    /// Components in topological order, dependencies first. Ties are broken by
+    /// level, then by name.
+    pub components: Vec<Vec<String>>,
+    /// Length of the longest dependency chain below each component; components
+    /// without dependencies are at level 0
+    pub levels: Vec<usize>,
+    /// Dependence edges between components, as indices into `components`
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl Condensation {
+    /// Index of the component containing a type
+    pub fn component_of(&self, name: &str) -> Option<usize> {
+        self.components
+            .iter()
+            .position(|c| c.iter().any(|n| n == name))
+    }
+}
+
+impl TypeMap {
+    /// Collapse every cycle into a single node
+    pub fn condensation(&self) -> Condensation {
+        let adj = self.adjacency(Direction::Dependencies);
+        let sccs = self.sccs();
+        let comp_of = sccs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, scc)| scc.iter().map(move |n| (n.as_str(), i)))
+            .collect::<HashMap<&str, usize>>();
+
+        // Tarjan emits dependencies first, so every edge points backwards and
+        // levels can be filled in a single pass
+        let mut edges = Set::new();
+        let mut levels = vec![0; sccs.len()];
+        for (i, scc) in sccs.iter().enumerate() {
+            for dest in scc.iter().flat_map(|n| &adj[n]) {
+                let j = comp_of[dest.as_str()];
+                if i != j {
+                    edges.insert((i, j));
+                    levels[i] = levels[i].max(levels[j] + 1);
+                }
+            }
+        }
+
+        // Renumber into a stable order
+        let mut order = (0..sccs.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&i| (levels[i], &sccs[i]));
+        let mut renumber = vec![0; sccs.len()];
+        for (new, &old) in order.iter().enumerate() {
+            renumber[old] = new;
+        }
+        let mut edges = edges
+            .into_iter()
+            .map(|(i, j)| (renumber[i], renumber[j]))
+            .collect::<Vec<_>>();
+        edges.sort();
+
+        Condensation {
+            components: order.iter().map(|&i| sccs[i].clone()).collect(),
+            levels: order.iter().map(|&i| levels[i]).collect(),
+            edges,
+        }
+    }
+
+    /// Every type, ordered so that each comes after everything it depends on.
+    /// Types in the same cycle are listed next to each other.
+    pub fn topological_order(&self) -> Vec<String> {
+        self.condensation()
+            .components
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// The strongly connected components of the graph, dependencies first:
+    /// no component depends on one that comes after it.
+    pub fn sccs(&self) -> Vec<Vec<String>> {
+        tarjan(&self.adjacency(Direction::Dependencies))
+    }
+
+    /// Every component that contains a cycle, including types that refer to
+    /// themselves.
+    pub fn cycles(&self) -> Vec<Cycle> {
+        let infinite = self
+            .inline_sccs()
+            .into_iter()
+            .flatten()
+            .collect::<Set<String>>();
+        let adj = self.adjacency(Direction::Dependencies);
+        self.sccs()
+            .into_iter()
+            .filter(|scc| is_cyclic(scc, &adj))
+            .map(|types| Cycle {
+                infinite: types.iter().any(|t| infinite.contains(t)),
+                types,
+            })
+            .collect()
+    }
+
+    /// Cyclic components of the graph restricted to inline (by-value) fields
+    fn inline_sccs(&self) -> Vec<Vec<String>> {
+        let adj = self
+            .info
+            .iter()
+            .map(|(name, info)| {
+                let deps = info
+                    .members
+                    .iter()
+                    .flat_map(|m| m.inline.iter().cloned())
+                    .collect::<Set<String>>();
+                (name.clone(), deps)
+            })
+            .collect::<BTreeMap<String, Set<String>>>();
+        tarjan(&adj)
+            .into_iter()
+            .filter(|scc| is_cyclic(scc, &adj))
+            .collect()
+    }
+}
+
+/// Color the types in each cycle, and the edges between them
+pub fn highlight_cycles(typemap: &TypeMap, cycles: &[Cycle]) -> Highlight {
+    let adj = typemap.adjacency(Direction::Dependencies);
+    let mut hl = Highlight::default();
+    for (i, cycle) in cycles.iter().enumerate() {
+        let color = if cycle.infinite {
+            "red"
+        } else {
+            PALETTE[i % PALETTE.len()]
+        };
+        for src in &cycle.types {
+            hl.nodes.insert(src.clone(), color.into());
+            for dest in adj[src].iter().filter(|d| cycle.types.contains(d)) {
+                hl.edges.insert((src.clone(), dest.clone()), color.into());
+            }
+        }
+    }
+    hl
+}
+
+/// Whether a component has a cycle: more than one type, or a self-reference
+fn is_cyclic(scc: &[String], adj: &BTreeMap<String, Set<String>>) -> bool {
+    scc.len() > 1 || adj.get(&scc[0]).is_some_and(|next| next.contains(&scc[0]))
+}
+
+/// Tarjan's algorithm, without recursion so deep graphs don't overflow the stack.
+/// Components come out in reverse topological order, each sorted by name.
+fn tarjan(adj: &BTreeMap<String, Set<String>>) -> Vec<Vec<String>> {
+    let names = adj.keys();
+    let sorted_next = |n: &str| {
+        adj.get(n)
+            .map(|s| s.iter().collect::<Vec<_>>())
+            .unwrap_or_default()
+    };
+
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut lowlink: HashMap<&str, usize> = HashMap::new();
+    let mut on_stack = Set::new();
+    let mut stack = vec![];
+    let mut sccs = vec![];
+
+    for root in names {
+        if index.contains_key(root.as_str()) {
+            continue;
+        }
+        // Each frame is a node and the neighbors still left to visit
+        let mut work = vec![(root.as_str(), sorted_next(root), 0)];
+        while let Some((n, next, i)) = work.last_mut() {
+            let n = *n;
+            if *i == 0 && !index.contains_key(n) {
+                let idx = index.len();
+                index.insert(n, idx);
+                lowlink.insert(n, idx);
+                stack.push(n);
+                on_stack.insert(n);
+            }
+            if let Some(m) = next.get(*i).map(|m| m.as_str()) {
+                *i += 1;
+                if !index.contains_key(m) {
+                    work.push((m, sorted_next(m), 0));
+                } else if on_stack.contains(m) {
+                    lowlink.insert(n, lowlink[n].min(index[m]));
+                }
+                continue;
+            }
+
+            work.pop();
+            if let Some((parent, _, _)) = work.last() {
+                lowlink.insert(parent, lowlink[parent].min(lowlink[n]));
+            }
+            if lowlink[n] == index[n] {
+                let mut scc = vec![];
+                while let Some(m) = stack.pop() {
+                    on_stack.remove(m);
+                    scc.push(m.to_string());
+                    if m == n {
+                        break;
+                    }
+                }
+                scc.sort();
+                sccs.push(scc);
+            }
+        }
+    }
+    sccs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_self_reference() {
+        let tm = TypeMap::build("examples/ex10.rs").unwrap();
+        assert_eq!(
+            tm.cycles(),
+            vec![Cycle {
+                types: vec!["A".into()],
+                infinite: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        let tm = TypeMap::build("examples/ex13.rs").unwrap();
+        let cycles = tm.cycles();
+        let find = |name: &str| cycles.iter().find(|c| c.types.contains(&name.into()));
+        assert_eq!(find("A").unwrap().types, vec!["A", "B"]);
+        assert!(!find("A").unwrap().infinite);
+        assert_eq!(find("C").unwrap().types, vec!["C", "D"]);
+        assert!(find("C").unwrap().infinite);
+        assert!(!find("E").unwrap().infinite);
+        assert_eq!(find("F"), None);
+        // `self::`, `crate::` and `Self` lead back to the types they name
+        assert_eq!(find("H").unwrap().types, vec!["H", "I"]);
+        assert!(find("H").unwrap().infinite);
+        assert_eq!(find("J").unwrap().types, vec!["J"]);
+        assert!(find("J").unwrap().infinite);
+        assert_eq!(cycles.len(), 5);
+    }
+
+    #[test]
+    fn test_condensation() {
+        let tm = TypeMap::build("examples/ex13.rs").unwrap();
+        let cond = tm.condensation();
+        let a = cond.component_of("A").unwrap();
+        let opt = cond.component_of("Option").unwrap();
+        assert_eq!(cond.components[a], vec!["A", "B"]);
+        assert_eq!(cond.levels[a], 1);
+        assert_eq!(cond.levels[opt], 0);
+        assert!(cond.edges.contains(&(a, opt)));
+        assert!(cond.edges.iter().all(|(i, j)| j < i));
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let tm = TypeMap::build("examples/ex05.rs").unwrap();
+        assert_eq!(
+            tm.topological_order(),
+            vec!["B", "i32", "usize", "D", "C", "A"]
+        );
+    }
+
+    #[test]
+    fn test_sccs_order() {
+        let tm = TypeMap::build("examples/ex03.rs").unwrap();
+        assert_eq!(tm.sccs(), vec![vec!["C"], vec!["B"], vec!["A"]]);
+    }
+}
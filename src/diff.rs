@@ -0,0 +1,210 @@
+//! Structural differences between two versions of a codebase.
+use super::{DepGraph, Dependence, Set, TypeMap};
+use crate::dot::Highlight;
+
+/// What changed between an old and a new `TypeMap`. Every list is sorted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TypeMapDiff {
+    pub added_types: Vec<String>,
+    pub removed_types: Vec<String>,
+    /// Types defined in both versions with a different kind, like a struct
+    /// that became an enum
+    pub changed_kinds: Vec<KindChange>,
+    pub added_edges: Vec<EdgeChange>,
+    pub removed_edges: Vec<EdgeChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KindChange {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EdgeChange {
+    pub from: String,
+    pub to: String,
+    /// `field` or `trait`, as in the JSON export
+    pub kind: String,
+}
+
+impl TypeMap {
+    /// Compare against a newer version of the same codebase
+    pub fn diff(&self, new: &TypeMap) -> TypeMapDiff {
+        let old_kinds = kinds(self);
+        let new_kinds = kinds(new);
+        let old_edges = edges(self);
+        let new_edges = edges(new);
+
+        let mut changed_kinds = old_kinds
+            .iter()
+            .filter_map(|(name, old)| {
+                let (_, new) = new_kinds.iter().find(|(n, _)| n == name)?;
+                (old != new).then(|| KindChange {
+                    name: name.clone(),
+                    old: old.clone(),
+                    new: new.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        changed_kinds.sort();
+
+        let names =
+            |kinds: &[(String, String)]| kinds.iter().map(|(n, _)| n.clone()).collect::<Set<_>>();
+        let (old_names, new_names) = (names(&old_kinds), names(&new_kinds));
+        TypeMapDiff {
+            added_types: new_names.difference(&old_names).cloned().collect(),
+            removed_types: old_names.difference(&new_names).cloned().collect(),
+            changed_kinds,
+            added_edges: new_edges.difference(&old_edges).cloned().collect(),
+            removed_edges: old_edges.difference(&new_edges).cloned().collect(),
+        }
+    }
+
+    /// Both versions in one graph, for drawing a diff. Types defined in both
+    /// take their kind and details from `new`.
+    pub fn union(&self, new: &TypeMap) -> TypeMap {
+        let mut graph = new.graph.clone();
+        let mut info = self.info.clone();
+        info.extend(new.info.clone());
+        for (src, dests) in &self.graph {
+            let key = match graph.keys().find(|k| k.name() == src.name()) {
+                Some(key) => key.clone(),
+                None => src.clone(),
+            };
+            graph.entry(key).or_default().extend(dests.iter().cloned());
+        }
+        TypeMap {
+            graph: dedup_dests(graph),
+            info,
+            diagnostics: vec![],
+        }
+    }
+}
+
+impl TypeMapDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// One line per change: `+` for additions, `-` for removals and `~` for
+    /// kind changes
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for name in &self.added_types {
+            out += &format!("+ type {name}\n");
+        }
+        for name in &self.removed_types {
+            out += &format!("- type {name}\n");
+        }
+        for c in &self.changed_kinds {
+            out += &format!("~ type {}: {} -> {}\n", c.name, c.old, c.new);
+        }
+        for e in &self.added_edges {
+            out += &format!("+ edge {} -> {} ({})\n", e.from, e.to, e.kind);
+        }
+        for e in &self.removed_edges {
+            out += &format!("- edge {} -> {} ({})\n", e.from, e.to, e.kind);
+        }
+        out
+    }
+
+    /// Colors for drawing the diff on `TypeMap::union`: additions green,
+    /// removals red and kind changes orange
+    pub fn highlight(&self) -> Highlight {
+        let mut hl = Highlight::default();
+        for (names, color) in [(&self.added_types, "green"), (&self.removed_types, "red")] {
+            for name in names {
+                hl.nodes.insert(name.clone(), color.into());
+            }
+        }
+        for c in &self.changed_kinds {
+            hl.nodes.insert(c.name.clone(), "orange".into());
+        }
+        for (edges, color) in [(&self.added_edges, "green"), (&self.removed_edges, "red")] {
+            for e in edges {
+                hl.edges
+                    .insert((e.from.clone(), e.to.clone()), color.into());
+            }
+        }
+        hl
+    }
+}
+
+/// (name, kind) of every user-defined type
+fn kinds(typemap: &TypeMap) -> Vec<(String, String)> {
+    typemap
+        .graph
+        .keys()
+        .map(|k| (k.name(), k.dep_type()))
+        .collect()
+}
+
+fn edges(typemap: &TypeMap) -> Set<EdgeChange> {
+    typemap
+        .sorted_edges()
+        .into_iter()
+        .map(|(src, dest)| EdgeChange {
+            from: src.name(),
+            to: dest.name(),
+            kind: edge_kind(dest).into(),
+        })
+        .collect()
+}
+
+fn edge_kind(dest: &Dependence) -> &'static str {
+    match dest {
+        Dependence::Field(..) => "field",
+        Dependence::Trait(..) => "trait",
+    }
+}
+
+/// Destinations are keyed by kind as well as name, so a dependency on a type
+/// whose kind changed would otherwise appear twice
+fn dedup_dests(graph: DepGraph) -> DepGraph {
+    graph
+        .into_iter()
+        .map(|(src, dests)| {
+            let mut seen = Set::new();
+            let dests = dests
+                .into_iter()
+                .filter(|d| seen.insert((d.name(), edge_kind(d))))
+                .collect();
+            (src, dests)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old = TypeMap::build("examples/ex16.rs").unwrap();
+        let new = TypeMap::build("examples/ex17.rs").unwrap();
+        let diff = old.diff(&new);
+        assert_eq!(diff.added_types, ["Cache", "Role"]);
+        assert_eq!(diff.removed_types, ["Session"]);
+        assert_eq!(
+            diff.changed_kinds,
+            [KindChange {
+                name: "Account".into(),
+                old: "struct".into(),
+                new: "enum".into(),
+            }]
+        );
+        assert!(diff.to_text().contains("+ edge Cache -> Store (trait)\n"));
+        assert!(diff.to_text().contains("+ edge User -> Role (field)\n"));
+        assert!(diff.to_text().contains("- edge Session -> User (field)\n"));
+        assert!(old.diff(&old).is_empty());
+
+        let union = old.union(&new);
+        assert_eq!(union.graph().len(), 6);
+        assert!(union.graph().keys().any(|k| k.name() == "Session"));
+        let hl = diff.highlight();
+        assert_eq!(hl.nodes["Session"], "red");
+        assert_eq!(hl.edges[&("User".to_string(), "Role".to_string())], "green");
+    }
+}
@@ -1,78 +1,488 @@
-use super::TypeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::ErrorKind;
+
+use super::{Dependence, Member, TypeInfo, TypeMap};
+use crate::graphml::escape;
+use crate::theme::{EdgeStyle, NodeStyle, Theme};
+use anyhow::{anyhow, bail, Result};
+use graphviz_rust::cmd;
 use graphviz_rust::dot_structures::*;
 use graphviz_rust::printer::PrinterContext;
-use graphviz_rust::{cmd, exec};
 
-pub fn generate_dot(typemap: &TypeMap, outfile: Option<&str>) -> String {
-    // Build nodes
-    let mut nodes = typemap
-        .graph()
-        .keys()
-        .enumerate()
-        .map(|(_i, n)| {
-            Stmt::Node(Node::new(
-                NodeId(Id::Plain(n.to_string()), None),
+/// What `render` can turn DOT source into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderFormat {
+    Dot,
+    Svg,
+    Png,
+    Pdf,
+}
+
+impl RenderFormat {
+    /// Infer the format from a file extension, like `typemap.svg`
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        Some(match ext.to_lowercase().as_str() {
+            "dot" | "gv" => Self::Dot,
+            "svg" => Self::Svg,
+            "png" => Self::Png,
+            "pdf" => Self::Pdf,
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for RenderFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Dot => "dot",
+            Self::Svg => "svg",
+            Self::Png => "png",
+            Self::Pdf => "pdf",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Extra colors to draw on top of the default rendering
+#[derive(Debug, Default, Clone)]
+pub struct Highlight {
+    /// Node name to color
+    pub nodes: BTreeMap<String, String>,
+    /// (source, destination) names to color
+    pub edges: BTreeMap<(String, String), String>,
+}
+
+impl Highlight {
+    fn node_attrs(&self, name: &str) -> Vec<Attribute> {
+        self.nodes
+            .get(name)
+            .map(|c| {
+                vec![
+                    Attribute(Id::Plain("color".into()), Id::Plain(c.clone())),
+                    Attribute(Id::Plain("penwidth".into()), Id::Plain("2".into())),
+                ]
+            })
+            .unwrap_or_default()
+    }
+
+    fn edge_attrs(&self, src: &str, dest: &str) -> Vec<Attribute> {
+        self.edges
+            .get(&(src.to_string(), dest.to_string()))
+            .map(|c| {
                 vec![
-                    Attribute(Id::Plain("shape".into()), Id::Plain("rect".into())),
+                    Attribute(Id::Plain("color".into()), Id::Plain(c.clone())),
+                    Attribute(Id::Plain("penwidth".into()), Id::Plain("2".into())),
+                ]
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// An ID, quoted unless it's a plain identifier (paths like `m::B` and colors
+/// like `#1e1e1e` aren't)
+fn id(s: &str) -> Id {
+    let plain = s.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !s.starts_with(|c: char| c.is_ascii_digit());
+    if plain {
+        Id::Plain(s.into())
+    } else {
+        Id::Escaped(format!("\"{}\"", s.replace('"', "\\\"")))
+    }
+}
+
+fn node_id(name: &str) -> NodeId {
+    NodeId(id(name), None)
+}
+
+fn attr(key: &str, value: &str) -> Attribute {
+    Attribute(Id::Plain(key.into()), id(value))
+}
+
+/// `attrs` with those set by `highlight` replaced
+fn with_highlight(mut attrs: Vec<Attribute>, highlight: Vec<Attribute>) -> Vec<Attribute> {
+    attrs.retain(|Attribute(k, _)| !highlight.iter().any(|Attribute(h, _)| h == k));
+    attrs.extend(highlight);
+    attrs
+}
+
+/// How `dot_graph` draws a `TypeMap`
+#[derive(Debug, Default, Clone)]
+pub struct DotOptions {
+    /// Extra colors to draw on top of the default rendering
+    pub highlight: Highlight,
+    /// Group the types of each module in a cluster, nested like the modules
+    pub clusters: bool,
+    /// Modules to draw as a single node, submodules included. Edges to and
+    /// from their types are merged and labeled with how many they stand for.
+    pub collapse: Vec<String>,
+    /// Draw types as UML-style records listing their fields and variants,
+    /// with field edges leaving from the row that causes them
+    pub records: bool,
+    pub theme: Theme,
+}
+
+impl DotOptions {
+    /// The collapsed module `module` belongs to, if any. The outermost one
+    /// wins when both a module and its submodule are collapsed.
+    fn collapsed(&self, module: &str) -> Option<&str> {
+        self.collapse
+            .iter()
+            .filter(|c| module == *c || module.starts_with(&format!("{c}::")))
+            .min_by_key(|c| c.len())
+            .map(String::as_str)
+    }
+}
+
+pub fn generate_dot(typemap: &TypeMap) -> String {
+    generate_dot_with(typemap, &DotOptions::default())
+}
+
+/// Like `generate_dot`, but with clustering, collapsing and highlighting
+pub fn generate_dot_with(typemap: &TypeMap, options: &DotOptions) -> String {
+    graphviz_rust::print(dot_graph(typemap, options), &mut PrinterContext::default())
+}
+
+/// The DOT syntax tree behind `generate_dot_with`, for passing to `render`
+pub fn dot_graph(typemap: &TypeMap, options: &DotOptions) -> Graph {
+    let highlight = &options.highlight;
+    let theme = &options.theme;
+    let module_of = |name: &str| typemap.info(name).map(|i| i.module.as_str());
+    // The node a type is drawn as: itself, or the module it is collapsed into
+    let endpoint = |name: &str| match module_of(name).and_then(|m| options.collapsed(m)) {
+        Some(c) => module_node(c),
+        None => name.to_string(),
+    };
+
+    // Node statements by the module whose cluster they go in
+    let mut members: BTreeMap<String, Vec<Stmt>> = BTreeMap::new();
+    let mut collapsed = BTreeSet::new();
+    let mut types = typemap.graph().keys().collect::<Vec<_>>();
+    types.sort_by_key(|k| k.name());
+    for n in types {
+        let name = n.name();
+        let module = module_of(&name).unwrap_or_default();
+        if let Some(c) = options.collapsed(module) {
+            collapsed.insert(c);
+            continue;
+        }
+        let style = theme.nodes.of(n.kind());
+        let header = format!("{}{}", keyword(&n.dep_type(), style), escape(&name));
+        let (shape, label) = if options.records {
+            let mut table = format!(
+                "<table border=\"0\" cellborder=\"1\" cellspacing=\"0\"><tr><td>{header}</td></tr>"
+            );
+            for (i, (text, _)) in typemap
+                .info(&name)
+                .map(rows)
+                .unwrap_or_default()
+                .iter()
+                .enumerate()
+            {
+                table += &format!(
+                    "<tr><td port=\"{}\" align=\"left\">{}</td></tr>",
+                    port(i),
+                    escape(text)
+                );
+            }
+            ("plain", format!("<{table}</table>>"))
+        } else {
+            (style.shape.as_str(), format!("<{header}>"))
+        };
+        members
+            .entry(module.to_string())
+            .or_default()
+            .push(Stmt::Node(Node::new(
+                node_id(&name),
+                with_highlight(
+                    [
+                        attr("shape", shape),
+                        Attribute(Id::Plain("label".into()), Id::Plain(label)),
+                    ]
+                    .into_iter()
+                    .chain(node_style(style))
+                    .collect(),
+                    highlight.node_attrs(&name),
+                ),
+            )));
+    }
+    for c in collapsed {
+        let parent = c.rsplit_once("::").map(|(p, _)| p).unwrap_or_default();
+        members
+            .entry(parent.to_string())
+            .or_default()
+            .push(Stmt::Node(Node::new(
+                node_id(&module_node(c)),
+                [
+                    attr("shape", &theme.nodes.module.shape),
                     Attribute(
                         Id::Plain("label".into()),
                         Id::Plain(format!(
-                            "<<font color=\"{}\">{} </font>{}>",
-                            n.color(),
-                            n.dep_type(),
-                            n.name()
+                            "<{}{}>",
+                            keyword("mod", &theme.nodes.module),
+                            escape(c)
                         )),
                     ),
-                    Attribute(Id::Plain("fontname".into()), Id::Plain("monospace".into())),
-                ],
-            ))
-        })
-        .collect::<Vec<Stmt>>();
-
-    // Build edges
-    let edges = typemap
-        .graph()
-        .into_iter()
-        .map(|(src, dests)| {
-            dests
+                ]
                 .into_iter()
-                .map(|dest| {
-                    let d = Vertex::N(NodeId(Id::Plain(dest.to_string()), None));
-                    Stmt::Edge(Edge {
-                        ty: EdgeTy::Pair(Vertex::N(NodeId(Id::Plain(src.to_string()), None)), d),
-                        attributes: vec![],
-                    })
-                })
-                .collect::<Vec<Stmt>>()
-        })
-        .flatten()
-        .collect::<Vec<Stmt>>();
+                .chain(node_style(&theme.nodes.module))
+                .collect(),
+            )));
+    }
 
-    let stmts = {
-        nodes.extend(edges);
-        nodes
-    };
+    // Highlighted nodes that are only ever depended on (like `String`) have
+    // no statement of their own yet
+    let keys = typemap.graph().keys().map(|n| n.name()).collect::<Vec<_>>();
+    let all = typemap.nodes();
+    members.entry(String::new()).or_default().extend(
+        highlight
+            .nodes
+            .keys()
+            .filter(|n| !keys.contains(n) && all.contains(*n))
+            .map(|n| Stmt::Node(Node::new(node_id(n), highlight.node_attrs(n)))),
+    );
+
+    // Build edges. Edges between uncollapsed types are drawn as they are,
+    // the rest are merged by endpoint.
+    let mut edges = Vec::new();
+    let mut merged: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for (src, dest) in typemap.sorted_edges() {
+        let (from, to) = (endpoint(&src.name()), endpoint(&dest.name()));
+        if from == src.name() && to == dest.name() {
+            let style = match dest {
+                Dependence::Field(..) => &theme.edges.field,
+                Dependence::Trait(..) => &theme.edges.r#trait,
+            };
+            let attrs = with_highlight(edge_style(style), highlight.edge_attrs(&from, &to));
+            // Field edges leave from each row that mentions the type
+            let ports = match (options.records, dest, typemap.info(&from)) {
+                (true, Dependence::Field(..), Some(info)) => rows(info)
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, ms))| ms.iter().any(|m| m.deps.contains(&to)))
+                    .map(|(i, _)| Some(port(i)))
+                    .collect(),
+                _ => vec![None],
+            };
+            for p in ports {
+                edges.push(edge(&from, p, &to, attrs.clone()));
+            }
+        } else if from != to {
+            *merged.entry((from, to)).or_default() += 1;
+        }
+    }
+    // Trait implementations aren't in the graph, but are drawn as well
+    for (ty, tr) in typemap.realizations() {
+        let (from, to) = (endpoint(&ty), endpoint(&tr));
+        if from == ty && to == tr {
+            let attrs = with_highlight(
+                edge_style(&theme.edges.r#impl),
+                highlight.edge_attrs(&from, &to),
+            );
+            edges.push(edge(&from, None, &to, attrs));
+        } else if from != to {
+            *merged.entry((from, to)).or_default() += 1;
+        }
+    }
+    for ((from, to), count) in merged {
+        let attrs = if count > 1 {
+            vec![Attribute(
+                Id::Plain("label".into()),
+                Id::Plain(count.to_string()),
+            )]
+        } else {
+            vec![]
+        };
+        edges.push(edge(&from, None, &to, attrs));
+    }
+
+    // Defaults, which also style the nodes that are only depended on
+    let external = &theme.nodes.external;
+    let mut stmts = vec![
+        Stmt::GAttribute(GraphAttributes::Graph(vec![
+            attr("rankdir", &theme.rankdir),
+            attr("bgcolor", &theme.background),
+            attr("fontname", &theme.font),
+            attr("fontcolor", &theme.foreground),
+        ])),
+        Stmt::GAttribute(GraphAttributes::Node(
+            [
+                attr("shape", &external.shape),
+                attr("fontname", &theme.font),
+                attr("fontcolor", &theme.foreground),
+                attr("color", &theme.foreground),
+            ]
+            .into_iter()
+            .chain(node_style(external))
+            .collect(),
+        )),
+        Stmt::GAttribute(GraphAttributes::Edge(vec![
+            attr("color", &theme.foreground),
+            attr("fontname", &theme.font),
+            attr("fontcolor", &theme.foreground),
+        ])),
+    ];
+    if options.clusters {
+        stmts.extend(cluster_stmts("", &mut members, theme));
+    } else {
+        stmts.extend(members.into_values().flatten());
+    }
+    stmts.extend(edges);
 
     // Create the graph
-    let dotgraph = Graph::DiGraph {
+    Graph::DiGraph {
         id: Id::Plain("typemap".into()),
         strict: false,
         stmts,
-    };
+    }
+}
 
-    // Output to pdf
-    let mut ctx = PrinterContext::default();
-    if let Some(outf) = outfile {
-        let args = vec![
-            cmd::CommandArg::Output(outf.into()),
-            cmd::CommandArg::Format(cmd::Format::Pdf),
+/// The kind keyword in front of a node's name, in the style's accent color
+fn keyword(kind: &str, style: &NodeStyle) -> String {
+    if style.accent.is_empty() {
+        format!("{kind} ")
+    } else {
+        format!("<font color=\"{}\">{kind} </font>", escape(&style.accent))
+    }
+}
+
+/// Attributes for the parts of a node style that differ from the defaults
+fn node_style(style: &NodeStyle) -> Vec<Attribute> {
+    let mut attrs = vec![];
+    if !style.color.is_empty() {
+        attrs.push(attr("color", &style.color));
+    }
+    if !style.fill.is_empty() {
+        attrs.push(attr("style", "filled"));
+        attrs.push(attr("fillcolor", &style.fill));
+    }
+    attrs
+}
+
+/// Attributes for the parts of an edge style that differ from the defaults
+fn edge_style(style: &EdgeStyle) -> Vec<Attribute> {
+    let mut attrs = vec![];
+    if !style.color.is_empty() {
+        attrs.push(attr("color", &style.color));
+    }
+    if style.style != "solid" {
+        attrs.push(attr("style", &style.style));
+    }
+    if style.arrowhead != "normal" {
+        attrs.push(attr("arrowhead", &style.arrowhead));
+    }
+    attrs
+}
+
+/// Name of the node a collapsed module is drawn as. `mod` is a keyword, so
+/// it can't clash with a type path.
+fn module_node(module: &str) -> String {
+    format!("mod {module}")
+}
+
+/// An edge, leaving from a record row when `port` is given
+fn edge(from: &str, port: Option<String>, to: &str, attributes: Vec<Attribute>) -> Stmt {
+    let NodeId(id, _) = node_id(from);
+    let from = NodeId(id, port.map(|p| Port(Some(Id::Plain(p)), None)));
+    Stmt::Edge(Edge {
+        ty: EdgeTy::Pair(Vertex::N(from), Vertex::N(node_id(to))),
+        attributes,
+    })
+}
+
+/// Port name of a record row
+fn port(row: usize) -> String {
+    format!("r{row}")
+}
+
+/// The rows of a record: one per field, like `b: Box<B>`, or one per enum
+/// variant with its payload, like `Square { corner: Point, side: f64 }`.
+/// Each row comes with the members it shows.
+fn rows(info: &TypeInfo) -> Vec<(String, Vec<&Member>)> {
+    let mut rows: Vec<(String, Vec<&Member>)> = vec![];
+    for m in &info.members {
+        match &m.variant {
+            None => rows.push((format!("{}: {}", m.name, m.ty), vec![m])),
+            Some(v) => match rows.last_mut() {
+                Some((_, ms)) if ms[0].variant.as_ref() == Some(v) => ms.push(m),
+                _ => rows.push((String::new(), vec![m])),
+            },
+        }
+    }
+    for (text, ms) in &mut rows {
+        let Some(v) = &ms[0].variant else { continue };
+        let tys = ms.iter().map(|m| m.ty.as_str()).collect::<Vec<_>>();
+        *text = if ms[0].name.is_empty() {
+            v.clone()
+        } else if ms[0].name.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("{v}({})", tys.join(", "))
+        } else {
+            let fields = ms.iter().map(|m| format!("{}: {}", m.name, m.ty));
+            format!("{v} {{ {} }}", fields.collect::<Vec<_>>().join(", "))
+        };
+    }
+    rows
+}
+
+/// The node statements of `module`, followed by a cluster for each of its
+/// submodules that has nodes somewhere inside it
+fn cluster_stmts(
+    module: &str,
+    members: &mut BTreeMap<String, Vec<Stmt>>,
+    theme: &Theme,
+) -> Vec<Stmt> {
+    let mut stmts = members.remove(module).unwrap_or_default();
+    let prefix = if module.is_empty() {
+        String::new()
+    } else {
+        format!("{module}::")
+    };
+    let children = members
+        .keys()
+        .filter_map(|m| m.strip_prefix(&prefix))
+        .map(|rest| format!("{prefix}{}", rest.split("::").next().unwrap()))
+        .collect::<BTreeSet<_>>();
+    for child in children {
+        let mut inner = vec![
+            Stmt::Attribute(attr("label", &child)),
+            Stmt::Attribute(attr("color", &theme.cluster)),
         ];
-        exec(dotgraph.clone(), &mut ctx, args).unwrap();
+        inner.extend(cluster_stmts(&child, members, theme));
+        stmts.push(Stmt::Subgraph(Subgraph {
+            id: Id::Escaped(format!("\"cluster_{child}\"")),
+            stmts: inner,
+        }));
     }
+    stmts
+}
 
-    // Get dot string
-    let graph_str = graphviz_rust::print(dotgraph, &mut ctx);
-    graph_str
+/// Render a graph to `format` with graphviz' `dot` executable. When it isn't
+/// installed, SVG falls back to a pure-Rust layout if the `builtin-svg` feature
+/// is enabled.
+pub fn render(graph: &Graph, format: RenderFormat) -> Result<Vec<u8>> {
+    let dot = graphviz_rust::print(graph.clone(), &mut PrinterContext::default());
+    let graphviz_format = match format {
+        RenderFormat::Dot => return Ok(dot.into_bytes()),
+        RenderFormat::Svg => cmd::Format::Svg,
+        RenderFormat::Png => cmd::Format::Png,
+        RenderFormat::Pdf => cmd::Format::Pdf,
+    };
+    match graphviz_rust::exec_dot(dot, vec![cmd::CommandArg::Format(graphviz_format)]) {
+        Ok(bytes) => Ok(bytes),
+        #[cfg(feature = "builtin-svg")]
+        Err(e) if e.kind() == ErrorKind::NotFound && format == RenderFormat::Svg => {
+            Ok(crate::svg::render_svg(graph).into_bytes())
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let hint = if cfg!(feature = "builtin-svg") {
+                ""
+            } else {
+                ", or build typemap with `--features builtin-svg` to render svg without it"
+            };
+            bail!("graphviz `dot` executable not found; install graphviz to render {format}{hint}")
+        }
+        Err(e) => Err(anyhow!("graphviz failed to render {format}: {e}")),
+    }
 }
 
 #[cfg(test)]
@@ -83,9 +493,136 @@ mod test {
     #[test]
     fn test_gen() {
         let tm = TypeMap::build("examples/ex06.rs").unwrap();
-        let dot = generate_dot(&tm, None);
+        let dot = generate_dot(&tm);
         println!("{dot}");
-        generate_dot(&tm, Some("tmp/test.pdf"));
+        // Rendering needs graphviz, which may not be installed
+        match render(&dot_graph(&tm, &DotOptions::default()), RenderFormat::Pdf) {
+            Ok(pdf) => assert!(pdf.starts_with(b"%PDF")),
+            Err(e) => assert!(e.to_string().contains("not found"), "{e}"),
+        }
+    }
+
+    #[test]
+    fn test_quoted_ids() {
+        let tm = TypeMap::build("examples/ex18.rs").unwrap();
+        let dot = generate_dot(&tm);
+        assert!(dot.contains("Store -> \"sqlx::PgPool\""));
+    }
+
+    #[test]
+    fn test_clusters() {
+        let tm = TypeMap::build("examples/ex15.rs").unwrap();
+        let options = DotOptions {
+            clusters: true,
+            ..Default::default()
+        };
+        let dot = generate_dot_with(&tm, &options);
+        let net = dot.find("subgraph \"cluster_net\" {").unwrap();
+        let proto = dot.find("subgraph \"cluster_net::proto\" {").unwrap();
+        let header = dot.find("Header[").unwrap();
+        let app = dot.find("App[").unwrap();
+        assert!(app < net && net < proto && proto < header);
+    }
+
+    #[test]
+    fn test_collapse() {
+        let tm = TypeMap::build("examples/ex15.rs").unwrap();
+        let options = DotOptions {
+            collapse: vec!["net".into()],
+            ..Default::default()
+        };
+        let dot = generate_dot_with(&tm, &options);
+        assert!(dot.contains("\"mod net\"[shape=folder"));
+        assert!(!dot.contains("Frame"));
+        // App uses `Client` and `Frame`, both in `net`
+        assert!(dot.contains("App -> \"mod net\" [label=2]"));
+        assert!(dot.contains("\"mod net\" -> Config\n"));
+
+        // Collapsing a submodule keeps the clusters around it
+        let options = DotOptions {
+            clusters: true,
+            collapse: vec!["net::proto".into()],
+            ..Default::default()
+        };
+        let dot = generate_dot_with(&tm, &options);
+        assert!(dot.contains("\"mod net::proto\"[shape=folder"));
+        assert!(dot.contains("subgraph \"cluster_net\""));
+        assert!(!dot.contains("cluster_net::proto"));
+    }
+
+    #[test]
+    fn test_records() {
+        let tm = TypeMap::build("examples/ex14.rs").unwrap();
+        let options = DotOptions {
+            records: true,
+            ..Default::default()
+        };
+        let dot = generate_dot_with(&tm, &options);
+        assert!(dot.contains("Canvas[shape=plain,"));
+        assert!(dot.contains("<td port=\"r0\" align=\"left\">shapes: Vec&lt;S&gt;</td>"));
+        assert!(
+            dot.contains("<td port=\"r1\" align=\"left\">Square { corner: Point, side: f64 }</td>")
+        );
+        assert!(dot.contains("<td port=\"r2\" align=\"left\">Empty</td>"));
+        // Field edges leave from their row, others from the whole node
+        assert!(dot.contains("Canvas:r1 -> Point\n"));
+        assert!(dot.contains("Polygon:r0 -> Point\n"));
+        assert!(dot.contains("Polygon:r1 -> Point\n"));
+        assert!(dot.contains("Canvas -> Shape [style=dashed]\n"));
+    }
+
+    #[test]
+    fn test_theme() {
+        let tm = TypeMap::build("examples/ex14.rs").unwrap();
+        let mut theme = Theme::dark();
+        theme.nodes.r#enum.fill = "navy".into();
+        theme.edges.field.color = "gray".into();
+        let options = DotOptions {
+            theme,
+            ..Default::default()
+        };
+        let dot = generate_dot_with(&tm, &options);
+        assert!(dot.contains("graph[rankdir=TB,bgcolor=\"#1e1e1e\","));
+        assert!(dot.contains("<font color=\"#61afef\">trait </font>Shape"));
+        assert!(dot.contains("Polygon[shape=rect,label=<<font color=\"#e5c07b\">enum </font>Polygon>,style=filled,fillcolor=navy]"));
+        assert!(dot.contains("Canvas -> Point [color=gray]\n"));
+        assert!(dot.contains("Circle -> Shape [style=dashed,arrowhead=empty]\n"));
+
+        // Highlights replace the theme's colors
+        let mut options = DotOptions::default();
+        options
+            .highlight
+            .edges
+            .insert(("Canvas".into(), "Shape".into()), "red".into());
+        let dot = generate_dot_with(&tm, &options);
+        assert!(dot.contains("Canvas -> Shape [style=dashed,color=red,penwidth=2]\n"));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let options = DotOptions {
+            clusters: true,
+            records: true,
+            ..Default::default()
+        };
+        for path in ["examples/ex13.rs", "examples/ex14.rs", "examples/ex15.rs"] {
+            let dots = (0..2)
+                .map(|_| generate_dot_with(&TypeMap::build(path).unwrap(), &options))
+                .collect::<Vec<_>>();
+            assert_eq!(dots[0], dots[1]);
+        }
+        let dot = generate_dot(&TypeMap::build("examples/ex14.rs").unwrap());
+        let canvas = dot.find("  Canvas[").unwrap();
+        let shape = dot.find("  Shape[").unwrap();
+        assert!(canvas < shape);
+        assert!(dot.find("  Canvas -> Point").unwrap() < dot.find("  Canvas -> Shape").unwrap());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(RenderFormat::from_path("out.SVG"), Some(RenderFormat::Svg));
+        assert_eq!(RenderFormat::from_path("out.gv"), Some(RenderFormat::Dot));
+        assert_eq!(RenderFormat::from_path("out"), None);
     }
 
     //#[test]
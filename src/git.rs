@@ -0,0 +1,100 @@
+//! Reading source files as of a git revision, without checking it out.
+//!
+//! Blobs are read with the `git` command from the repository the file is in,
+//! so only local repositories are supported.
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::TypeMap;
+
+/// The contents of `path` at revision `rev`, like `HEAD~3` or `main`.
+/// `path` is relative to the current directory, as on the command line.
+pub fn read_at_rev(path: &str, rev: &str) -> Result<String> {
+    let path = Path::new(path);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow!("`{}` is not a file", path.display()))?;
+
+    // `./` makes the path relative to `dir` instead of the repository root
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["show", &format!("{rev}:./{name}")])
+        .output()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => anyhow!("`git` executable not found"),
+            _ => anyhow!("failed to run git: {e}"),
+        })?;
+    if !output.status.success() {
+        bail!(
+            "could not read `{}` at `{rev}`: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+impl TypeMap {
+    /// Build a `TypeMap` from a file as of a git revision. Spans name the
+    /// file as `rev:path`.
+    pub fn build_at_rev(path: &str, rev: &str) -> Result<Self> {
+        Self::from_source(&read_at_rev(path, rev)?, &format!("{rev}:{path}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=typemap",
+                "-c",
+                "user.email=typemap@localhost",
+            ])
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[test]
+    fn test_build_at_rev() {
+        let dir = std::env::temp_dir().join(format!("typemap-git-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        let file = dir.join("src/lib.rs");
+        git(&dir, &["init", "-q"]);
+        std::fs::copy("examples/ex16.rs", &file).unwrap();
+        git(&dir, &["add", "."]);
+        git(&dir, &["commit", "-q", "-m", "old"]);
+        std::fs::copy("examples/ex17.rs", &file).unwrap();
+
+        let path = file.to_str().unwrap();
+        let old = TypeMap::build_at_rev(path, "HEAD").unwrap();
+        let new = TypeMap::build(path).unwrap();
+        assert!(old.info("Session").is_some());
+        assert!(new.info("Session").is_none());
+        assert_eq!(
+            old.info("User").unwrap().span.as_ref().unwrap().file,
+            format!("HEAD:{path}")
+        );
+
+        let err = TypeMap::build_at_rev(path, "HEAD~5").unwrap_err();
+        assert!(err.to_string().contains("at `HEAD~5`"), "{err}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+//! GraphML export for graph tools like Gephi and yEd.
+use std::collections::HashMap;
+
+use super::{Dependence, TypeMap};
+
+/// Attributes declared in the GraphML header: (id, element, name, type)
+const KEYS: [(&str, &str, &str, &str); 11] = [
+    ("label", "node", "label", "string"),
+    ("kind", "node", "kind", "string"),
+    ("module", "node", "module", "string"),
+    ("visibility", "node", "visibility", "string"),
+    ("fan_in", "node", "fan_in", "int"),
+    ("fan_out", "node", "fan_out", "int"),
+    ("depth", "node", "depth", "int"),
+    ("closure", "node", "closure", "int"),
+    ("instability", "node", "instability", "double"),
+    ("edge_kind", "edge", "kind", "string"),
+    ("via", "edge", "via", "string"),
+];
+
+/// Render a `TypeMap` as GraphML. User-defined types carry their kind,
+/// module, visibility and coupling metrics; types that are only depended on
+/// have kind `external`. Edges carry their kind (`field` or `trait`) and the
+/// members that cause them.
+pub fn generate_graphml(typemap: &TypeMap) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n"
+    ));
+    for (id, element, name, ty) in KEYS {
+        out += &format!(
+            "  <key id=\"{id}\" for=\"{element}\" attr.name=\"{name}\" attr.type=\"{ty}\"/>\n"
+        );
+    }
+    out += "  <graph id=\"typemap\" edgedefault=\"directed\">\n";
+
+    let kinds = typemap
+        .graph()
+        .keys()
+        .map(|k| (k.name(), k.dep_type()))
+        .collect::<HashMap<_, _>>();
+    let metrics = typemap.metrics();
+    let mut nodes = Vec::from_iter(typemap.nodes());
+    nodes.sort();
+    for name in nodes {
+        let mut data = vec![
+            ("label", name.clone()),
+            (
+                "kind",
+                kinds.get(&name).cloned().unwrap_or("external".into()),
+            ),
+        ];
+        if let Some(info) = typemap.info(&name) {
+            data.push(("module", info.module.clone()));
+            data.push(("visibility", info.visibility.clone()));
+        }
+        if let Some(m) = metrics.types.iter().find(|m| m.name == name) {
+            data.push(("fan_in", m.fan_in.to_string()));
+            data.push(("fan_out", m.fan_out.to_string()));
+            data.push(("depth", m.depth.to_string()));
+            data.push(("closure", m.closure.to_string()));
+            data.push(("instability", m.instability.to_string()));
+        }
+        out += &format!("    <node id=\"{}\">\n", escape(&name));
+        out += &data_lines(&data);
+        out += "    </node>\n";
+    }
+
+    for (i, (src, dest)) in typemap.sorted_edges().into_iter().enumerate() {
+        let kind = match dest {
+            Dependence::Field(..) => "field",
+            Dependence::Trait(..) => "trait",
+        };
+        let via = typemap.dependence_causes(&src.name(), dest).join(", ");
+        out += &format!(
+            "    <edge id=\"e{i}\" source=\"{}\" target=\"{}\">\n",
+            escape(&src.name()),
+            escape(&dest.name())
+        );
+        out += &data_lines(&[("edge_kind", kind.into()), ("via", via)]);
+        out += "    </edge>\n";
+    }
+
+    out + "  </graph>\n</graphml>\n"
+}
+
+fn data_lines(data: &[(&str, String)]) -> String {
+    data.iter()
+        .map(|(key, value)| format!("      <data key=\"{key}\">{}</data>\n", escape(value)))
+        .collect()
+}
+
+/// Escape text for use in XML content and attribute values
+pub(crate) fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_graphml() {
+        let tm = TypeMap::build("examples/ex14.rs").unwrap();
+        let xml = generate_graphml(&tm);
+        assert!(xml.contains("<graph id=\"typemap\" edgedefault=\"directed\">"));
+        assert!(xml.contains(
+            "    <node id=\"Canvas\">\n      <data key=\"label\">Canvas</data>\n      <data key=\"kind\">struct</data>\n"
+        ));
+        assert!(xml.contains("    <node id=\"f64\">\n      <data key=\"label\">f64</data>\n      <data key=\"kind\">external</data>\n    </node>\n"));
+        assert!(xml.contains(
+            "source=\"Canvas\" target=\"Shape\">\n      <data key=\"edge_kind\">trait</data>\n      <data key=\"via\">S: Shape</data>\n"
+        ));
+        assert!(xml.contains("<data key=\"fan_in\">2</data>"));
+    }
+}
@@ -0,0 +1,357 @@
+//! Self-contained interactive HTML viewer.
+//!
+//! The page embeds the JSON document from `TypeMap::to_json` along with a
+//! precomputed layout, and draws it as SVG with inline JavaScript, so it works
+//! offline and without graphviz.
+use std::collections::BTreeMap;
+
+use super::TypeMap;
+use crate::theme::{EdgeStyle, NodeStyle, Theme};
+
+/// Horizontal distance between dependency levels
+const COLUMN_WIDTH: usize = 220;
+/// Vertical distance between types on the same level
+const ROW_HEIGHT: usize = 60;
+
+const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>typemap</title>
+<style>
+  html, body { margin: 0; height: 100%; }
+  body { display: flex; }
+  #main { flex: 1; display: flex; flex-direction: column; }
+  #toolbar { padding: 6px; border-bottom: 1px solid #ccc; }
+  #search { width: 240px; font-family: inherit; }
+  #graph { flex: 1; cursor: grab; user-select: none; }
+  #graph.dragging { cursor: grabbing; }
+  #panel { width: 320px; padding: 8px 12px; border-left: 1px solid #ccc; overflow: auto; }
+  #panel h2 { margin: 4px 0; font-size: 16px; }
+  #panel table { border-collapse: collapse; }
+  #panel td { padding: 1px 6px 1px 0; vertical-align: top; }
+  .node text { font-size: 12px; dominant-baseline: middle; text-anchor: middle; }
+  .edge { fill: none; }
+__THEME__
+  .faded { opacity: 0.15; }
+  .node.selected rect { stroke: red; stroke-width: 3; }
+  .node.match rect { fill: #fff3a0; }
+  .node.dependency rect, .edge.dependency { stroke: blue; stroke-width: 2; }
+  .node.dependent rect, .edge.dependent { stroke: darkgreen; stroke-width: 2; }
+</style>
+</head>
+<body>
+<div id="main">
+  <div id="toolbar">
+    <input id="search" placeholder="Search types" autocomplete="off">
+    <span id="hint">scroll to zoom, drag to pan, click a type for details</span>
+  </div>
+  <svg id="graph" xmlns="http://www.w3.org/2000/svg">
+    <defs>
+      <marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8" markerHeight="8" orient="auto">
+        <path d="M0,0 L10,5 L0,10 z" fill="#888"/>
+      </marker>
+    </defs>
+    <g id="viewport"></g>
+  </svg>
+</div>
+<div id="panel"><p>Select a type.</p></div>
+<script type="application/json" id="typemap">__TYPEMAP__</script>
+<script type="application/json" id="layout">__LAYOUT__</script>
+<script>
+"use strict";
+const data = JSON.parse(document.getElementById("typemap").textContent);
+const layout = JSON.parse(document.getElementById("layout").textContent);
+const NS = "http://www.w3.org/2000/svg";
+const svg = document.getElementById("graph");
+const viewport = document.getElementById("viewport");
+const panel = document.getElementById("panel");
+const info = new Map(data.nodes.map(n => [n.name, n]));
+const out = new Map(), inc = new Map();
+for (const name of Object.keys(layout)) { out.set(name, []); inc.set(name, []); }
+for (const e of data.edges) { out.get(e.from).push(e); inc.get(e.to).push(e); }
+
+function el(tag, attrs, parent) {
+  const e = document.createElementNS(NS, tag);
+  for (const [k, v] of Object.entries(attrs)) e.setAttribute(k, v);
+  parent.appendChild(e);
+  return e;
+}
+
+// Draw edges first so nodes sit on top of them
+const W = 160, H = 30;
+const edgeEls = data.edges.map(e => {
+  const [x1, y1] = layout[e.from], [x2, y2] = layout[e.to];
+  const path = el("path", {
+    class: "edge " + e.kind,
+    d: `M${x1 - W / 2},${y1} C${x1 - W},${y1} ${x2 + W},${y2} ${x2 + W / 2},${y2}`,
+    "marker-end": "url(#arrow)",
+  }, viewport);
+  el("title", {}, path).textContent = e.via.join(", ");
+  return { edge: e, el: path };
+});
+const nodeEls = new Map();
+for (const [name, [x, y]] of Object.entries(layout)) {
+  const n = info.get(name);
+  const g = el("g", { class: "node " + (n ? n.kind : "external"), transform: `translate(${x},${y})` }, viewport);
+  el("rect", { x: -W / 2, y: -H / 2, width: W, height: H, rx: 4 }, g);
+  const label = el("text", {}, g);
+  if (n) el("tspan", { class: "kind" }, label).textContent = n.kind + " ";
+  label.appendChild(document.createTextNode(name));
+  g.addEventListener("click", ev => { ev.stopPropagation(); select(name); });
+  nodeEls.set(name, g);
+}
+
+// Pan and zoom
+let view = { x: 0, y: 0, k: 1 };
+function applyView() { viewport.setAttribute("transform", `translate(${view.x},${view.y}) scale(${view.k})`); }
+function fit() {
+  const box = viewport.getBBox(), r = svg.getBoundingClientRect();
+  if (!box.width || !box.height) return;
+  view.k = Math.min(r.width / (box.width + 40), r.height / (box.height + 40), 2);
+  view.x = (r.width - box.width * view.k) / 2 - box.x * view.k;
+  view.y = (r.height - box.height * view.k) / 2 - box.y * view.k;
+  applyView();
+}
+svg.addEventListener("wheel", ev => {
+  ev.preventDefault();
+  const r = svg.getBoundingClientRect(), mx = ev.clientX - r.left, my = ev.clientY - r.top;
+  const f = Math.exp(-ev.deltaY * 0.001);
+  view.x = mx - (mx - view.x) * f;
+  view.y = my - (my - view.y) * f;
+  view.k *= f;
+  applyView();
+}, { passive: false });
+let drag = null;
+svg.addEventListener("mousedown", ev => { drag = { x: ev.clientX - view.x, y: ev.clientY - view.y, moved: false }; svg.classList.add("dragging"); });
+window.addEventListener("mousemove", ev => {
+  if (!drag) return;
+  drag.moved = true;
+  view.x = ev.clientX - drag.x;
+  view.y = ev.clientY - drag.y;
+  applyView();
+});
+window.addEventListener("mouseup", () => { svg.classList.remove("dragging"); setTimeout(() => { drag = null; }); });
+svg.addEventListener("click", () => { if (!drag || !drag.moved) select(null); });
+
+// Everything reachable from `name` along `adj`, which is `out` or `inc`
+function reach(name, adj, key) {
+  const seen = new Set([name]), stack = [name];
+  while (stack.length) {
+    for (const e of adj.get(stack.pop())) {
+      const next = e[key];
+      if (!seen.has(next)) { seen.add(next); stack.push(next); }
+    }
+  }
+  seen.delete(name);
+  return seen;
+}
+
+function select(name) {
+  for (const g of nodeEls.values()) g.classList.remove("selected", "dependency", "dependent", "faded");
+  for (const { el } of edgeEls) el.classList.remove("dependency", "dependent", "faded");
+  if (name === null) { panel.innerHTML = "<p>Select a type.</p>"; return; }
+  const deps = reach(name, out, "to"), users = reach(name, inc, "from");
+  for (const [n, g] of nodeEls) {
+    if (n === name) g.classList.add("selected");
+    else if (deps.has(n)) g.classList.add("dependency");
+    else if (users.has(n)) g.classList.add("dependent");
+    else g.classList.add("faded");
+  }
+  for (const { edge, el } of edgeEls) {
+    if ((edge.from === name || deps.has(edge.from)) && deps.has(edge.to)) el.classList.add("dependency");
+    else if ((edge.to === name || users.has(edge.to)) && users.has(edge.from)) el.classList.add("dependent");
+    else el.classList.add("faded");
+  }
+  showPanel(name, deps, users);
+}
+
+function text(parent, tag, content) {
+  const e = document.createElement(tag);
+  e.textContent = content;
+  parent.appendChild(e);
+  return e;
+}
+
+function showPanel(name, deps, users) {
+  panel.innerHTML = "";
+  const n = info.get(name);
+  text(panel, "h2", name);
+  if (!n) {
+    text(panel, "p", "Not defined in the analyzed source.");
+  } else {
+    const vis = n.visibility ? n.visibility + " " : "";
+    text(panel, "p", vis + n.kind + (n.module ? " in " + n.module : ""));
+    if (n.span) text(panel, "p", `${n.span.file}:${n.span.line}:${n.span.column}`);
+    for (const [title, members] of [["Generics", n.generics], ["Members", n.members]]) {
+      if (!members.length) continue;
+      text(panel, "h3", title);
+      const table = text(panel, "table", "");
+      for (const m of members) {
+        const row = table.insertRow();
+        const label = m.variant === null ? m.name : m.variant + (m.name ? "." + m.name : "");
+        row.insertCell().textContent = label;
+        row.insertCell().textContent = m.ty;
+      }
+    }
+    if (n.impls.length) { text(panel, "h3", "Implements"); text(panel, "p", n.impls.join(", ")); }
+  }
+  for (const [title, set] of [["Dependencies", deps], ["Dependents", users]]) {
+    text(panel, "h3", `${title} (${set.size})`);
+    const list = text(panel, "ul", "");
+    for (const d of [...set].sort()) {
+      const a = text(text(list, "li", ""), "a", d);
+      a.href = "#";
+      a.addEventListener("click", ev => { ev.preventDefault(); select(d); });
+    }
+  }
+}
+
+// Search highlights matching types, and Enter selects the first match
+const search = document.getElementById("search");
+function matches() {
+  const q = search.value.trim().toLowerCase();
+  return q ? [...nodeEls.keys()].filter(n => n.toLowerCase().includes(q)) : [];
+}
+search.addEventListener("input", () => {
+  const found = new Set(matches());
+  for (const [n, g] of nodeEls) g.classList.toggle("match", found.has(n));
+});
+search.addEventListener("keydown", ev => {
+  if (ev.key !== "Enter") return;
+  const [first] = matches();
+  if (first === undefined) return;
+  select(first);
+  const [x, y] = layout[first], r = svg.getBoundingClientRect();
+  view.x = r.width / 2 - x * view.k;
+  view.y = r.height / 2 - y * view.k;
+  applyView();
+});
+
+window.addEventListener("resize", fit);
+fit();
+</script>
+</body>
+</html>
+"##;
+
+/// Render a `TypeMap` as a single HTML page with no external resources.
+/// Types are laid out in columns by dependency level, dependencies on the
+/// right, and the page supports panning, zooming, searching and inspecting
+/// a type's members, location, dependencies and dependents. The theme's
+/// colors, font and styles apply, but not its `rankdir`.
+pub fn generate_html(typemap: &TypeMap, theme: &Theme) -> String {
+    TEMPLATE
+        .replace("__THEME__\n", &theme_css(theme))
+        .replace("__TYPEMAP__", &script_safe(&typemap.to_json()))
+        .replace("__LAYOUT__", &script_safe(&layout_json(typemap)))
+}
+
+/// Node positions as `{ "name": [x, y] }`, one column per condensation
+/// level with types in topological order within it
+fn layout_json(typemap: &TypeMap) -> String {
+    let cond = typemap.condensation();
+    let max_level = cond.levels.iter().copied().max().unwrap_or(0);
+    let mut rows = vec![0; max_level + 1];
+    let mut layout = BTreeMap::new();
+    for (level, types) in cond.levels.iter().zip(&cond.components) {
+        for name in types {
+            let x = (max_level - level) * COLUMN_WIDTH;
+            let y = rows[*level] * ROW_HEIGHT;
+            rows[*level] += 1;
+            layout.insert(name.clone(), [x, y]);
+        }
+    }
+    serde_json::to_string(&layout).expect("layouts are always serializable")
+}
+
+/// CSS rules for the theme's colors, font and node and edge styles
+fn theme_css(theme: &Theme) -> String {
+    let or = |color: &str, fallback: &str| {
+        if color.is_empty() {
+            fallback.to_string()
+        } else {
+            color.to_string()
+        }
+    };
+    let mut css = format!(
+        "  body {{ background: {}; color: {}; font-family: {}; }}\n",
+        theme.background, theme.foreground, theme.font
+    );
+    css += &format!("  .node text {{ fill: {}; }}\n", theme.foreground);
+    css += &format!("  #arrow path {{ fill: {}; }}\n", theme.foreground);
+    let nodes = &theme.nodes;
+    let kinds: [(&str, &NodeStyle); 7] = [
+        ("struct", &nodes.r#struct),
+        ("enum", &nodes.r#enum),
+        ("union", &nodes.r#union),
+        ("type", &nodes.r#type),
+        ("trait", &nodes.r#trait),
+        ("temp", &nodes.temp),
+        ("external", &nodes.external),
+    ];
+    for (kind, style) in kinds {
+        css += &format!(
+            "  .node.{kind} rect {{ fill: {}; stroke: {}; }}\n",
+            or(&style.fill, &theme.background),
+            or(&style.color, &theme.foreground)
+        );
+        if !style.accent.is_empty() {
+            css += &format!("  .node.{kind} .kind {{ fill: {}; }}\n", style.accent);
+        }
+    }
+    let edges: [(&str, &EdgeStyle); 2] = [
+        ("field", &theme.edges.field),
+        ("trait", &theme.edges.r#trait),
+    ];
+    for (kind, style) in edges {
+        let line = match style.style.as_str() {
+            "dashed" => "stroke-dasharray: 5 3;",
+            "dotted" => "stroke-dasharray: 2 3;",
+            "bold" => "stroke-width: 2;",
+            _ => "",
+        };
+        css += &format!(
+            "  .edge.{kind} {{ stroke: {}; {line} }}\n",
+            or(&style.color, &theme.foreground)
+        );
+    }
+    css
+}
+
+/// JSON can't end a `<script>` element early once `</` is escaped
+fn script_safe(json: &str) -> String {
+    json.replace("</", "<\\/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_html() {
+        let tm = TypeMap::build("examples/ex14.rs").unwrap();
+        let html = generate_html(&tm, &Theme::dark());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("__TYPEMAP__") && !html.contains("__LAYOUT__"));
+        assert!(html.contains("  body { background: #1e1e1e;"));
+        assert!(html.contains("  .node.trait .kind { fill: #61afef; }"));
+        // Self-contained: nothing is fetched
+        assert!(!html.contains("src=\"http") && !html.contains("href=\"http"));
+
+        let start = html.find("id=\"layout\">").unwrap() + "id=\"layout\">".len();
+        let end = start + html[start..].find("</script>").unwrap();
+        let layout: BTreeMap<String, [usize; 2]> = serde_json::from_str(&html[start..end]).unwrap();
+        assert_eq!(layout.len(), tm.nodes().len());
+        // Canvas depends on Point, so it is drawn further left
+        assert!(layout["Canvas"][0] < layout["Point"][0]);
+    }
+
+    #[test]
+    fn test_script_safe() {
+        assert_eq!(
+            script_safe(r#"{"ty":"</script>"}"#),
+            r#"{"ty":"<\/script>"}"#
+        );
+    }
+}
@@ -0,0 +1,555 @@
+//! Finding the source files to analyze: stdin, files, globs and directories,
+//! along with the module files they declare with `mod name;`.
+//!
+//! Module files are looked up the way rustc does: `name.rs` or `name/mod.rs`
+//! next to a crate root or `mod.rs` file, under a directory named after any
+//! other file, or at the path given by a `#[path]` attribute.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use syn::{Expr, ExprLit, Item, ItemMod, Lit, Meta};
+
+use super::{Set, TypeMap};
+use crate::cfg::Cfg;
+use crate::git::read_at_rev;
+
+/// Files that make a directory a crate, in order of preference
+const CRATE_ROOTS: [&str; 4] = ["src/lib.rs", "src/main.rs", "lib.rs", "main.rs"];
+
+/// A parsed source file, and the module it defines
+#[derive(Clone)]
+pub(crate) struct Parsed {
+    pub ast: syn::File,
+    /// File name used in spans and diagnostics
+    pub src: String,
+    /// Path of the module, like `a::b`. Empty for the crate root.
+    pub module: String,
+}
+
+/// Files parsed by earlier builds, so that rebuilding only parses the files
+/// that changed, and the paths the builds looked at. Only use a cache with
+/// one `Cfg`.
+#[derive(Default)]
+pub struct Cache {
+    /// Source and parse of each file, by path
+    parsed: BTreeMap<PathBuf, (String, Parsed)>,
+    /// Files read or looked for, including missing module files, and the
+    /// directories they are in
+    watched: Set<PathBuf>,
+}
+
+impl Cache {
+    /// The files and directories whose changes would change the builds since
+    /// the last call to `clear_watched`
+    pub fn watched(&self) -> &Set<PathBuf> {
+        &self.watched
+    }
+
+    /// Watch `path`, for inputs that aren't parsed, like saved typemaps
+    pub(crate) fn watch(&mut self, path: &Path) {
+        self.watched.insert(path.to_path_buf());
+    }
+
+    /// Start recording paths anew, and forget the files that weren't used
+    pub fn clear_watched(&mut self) {
+        let watched = std::mem::take(&mut self.watched);
+        self.parsed.retain(|path, _| watched.contains(path));
+    }
+
+    /// Parse `source`, unless it's the same as when `path` was last parsed
+    fn parse(
+        &mut self,
+        path: &Path,
+        source: &str,
+        src: &str,
+        module: &str,
+        cfg: &Cfg,
+    ) -> Result<Parsed> {
+        if let Some((cached, parsed)) = self.parsed.get(path) {
+            if cached == source && parsed.src == src && parsed.module == module {
+                return Ok(parsed.clone());
+            }
+        }
+        let parsed = parse(source, src, module, cfg)?;
+        self.parsed
+            .insert(path.into(), (source.into(), parsed.clone()));
+        Ok(parsed)
+    }
+}
+
+/// A file to start analyzing from
+struct Root {
+    path: PathBuf,
+    module: String,
+}
+
+impl TypeMap {
+    /// Build one `TypeMap` from several inputs, as of a git revision if one
+    /// is given. Each input is one of:
+    ///
+    /// - `-`, to read a single file from stdin
+    /// - a file, whose module files are analyzed too
+    /// - a glob like `src/**/*.rs`
+    /// - a directory with a crate root like `src/lib.rs`, or else every `.rs`
+    ///   file underneath it, with modules named after their paths
+    ///
+    /// A file that another input declares as a module is only analyzed as
+    /// that module. Only files can be read at a revision.
+    pub fn build_inputs(inputs: &[impl AsRef<str>], rev: Option<&str>, cfg: &Cfg) -> Result<Self> {
+        Self::build_cached(inputs, rev, cfg, &mut Cache::default())
+    }
+
+    /// Like `build_inputs`, but only parse the files that changed since they
+    /// were put in `cache`, and record the paths to watch for changes there.
+    /// Builds at a revision don't use the cache.
+    pub fn build_cached(
+        inputs: &[impl AsRef<str>],
+        rev: Option<&str>,
+        cfg: &Cfg,
+        cache: &mut Cache,
+    ) -> Result<Self> {
+        if let Some(rev) = rev {
+            let read = |path: &Path| read_at_rev(&path.to_string_lossy(), rev);
+            return Self::build_with(inputs, Some(rev), cfg, &mut Cache::default(), &read);
+        }
+        let tried = RefCell::new(Set::new());
+        let read = |path: &Path| {
+            tried.borrow_mut().insert(path.to_path_buf());
+            Ok(std::fs::read_to_string(path)?)
+        };
+        let result = Self::build_with(inputs, None, cfg, cache, &read);
+
+        // Files can appear in the directories of both found and missing ones
+        let tried = tried.into_inner();
+        for path in &tried {
+            if let Some(dir) = path.parent() {
+                cache.watched.insert(dir.to_path_buf());
+            }
+        }
+        cache.watched.extend(tried);
+        for input in inputs {
+            let input = Path::new(input.as_ref());
+            if input.is_dir() {
+                let mut dirs = vec![];
+                sub_dirs(input, &mut dirs)?;
+                cache.watched.extend(dirs);
+            }
+        }
+        result
+    }
+
+    fn build_with(
+        inputs: &[impl AsRef<str>],
+        rev: Option<&str>,
+        cfg: &Cfg,
+        cache: &mut Cache,
+        read: &dyn Fn(&Path) -> Result<String>,
+    ) -> Result<Self> {
+        let mut files = vec![];
+        let mut roots = vec![];
+        for input in inputs {
+            let input = input.as_ref();
+            if input == "-" {
+                let mut source = String::new();
+                std::io::stdin().read_to_string(&mut source)?;
+                files.push(parse(&source, "<stdin>", "", cfg)?);
+            } else if rev.is_some() {
+                if Path::new(input).is_dir() {
+                    bail!("only files can be read at a revision (`{input}` is a directory)");
+                }
+                if input.contains(['*', '?', '[']) {
+                    bail!("only files can be read at a revision (`{input}` is a glob)");
+                }
+                roots.push(Root {
+                    path: input.into(),
+                    module: String::new(),
+                });
+            } else {
+                roots.extend(expand(input)?);
+            }
+        }
+
+        let label = |path: &Path| match rev {
+            Some(rev) => format!("{rev}:{}", path.display()),
+            None => path.display().to_string(),
+        };
+
+        // Analyze the modules of each root, then drop roots that are a
+        // module of another one
+        let mut trees = vec![];
+        for root in roots {
+            trees.push(follow(root, read, &label, cfg, cache)?);
+        }
+        let declared = trees
+            .iter()
+            .flat_map(|tree| tree.iter().skip(1).map(|(path, _)| path.clone()))
+            .collect::<Set<_>>();
+        let mut seen = Set::new();
+        for tree in trees {
+            if declared.contains(&tree[0].0) {
+                continue;
+            }
+            for (path, parsed) in tree {
+                if seen.insert(path) {
+                    files.push(parsed);
+                }
+            }
+        }
+        Ok(Self::from_files(&files))
+    }
+}
+
+/// The roots an input on the command line stands for
+fn expand(input: &str) -> Result<Vec<Root>> {
+    let root = |path: PathBuf| Root {
+        path,
+        module: String::new(),
+    };
+    if input.contains(['*', '?', '[']) {
+        let mut paths = glob::glob(input)?
+            .filter_map(|p| p.ok())
+            .filter(|p| p.is_file())
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            bail!("no files match `{input}`");
+        }
+        paths.sort();
+        return Ok(paths.into_iter().map(root).collect());
+    }
+
+    let path = Path::new(input);
+    if !path.is_dir() {
+        return Ok(vec![root(path.into())]);
+    }
+    if let Some(file) = CRATE_ROOTS
+        .iter()
+        .map(|r| path.join(r))
+        .find(|p| p.is_file())
+    {
+        return Ok(vec![root(file)]);
+    }
+    let mut files = vec![];
+    rust_files(path, &mut files)?;
+    if files.is_empty() {
+        bail!("no `.rs` files in `{input}`");
+    }
+    files.sort();
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            let module = module_of(file.strip_prefix(path).unwrap_or(&file));
+            Root { path: file, module }
+        })
+        .collect())
+}
+
+/// Every `.rs` file under `dir`, recursively
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            rust_files(&path, files)?;
+        } else if path.extension().is_some_and(|e| e == "rs") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `dir` and every directory underneath it
+fn sub_dirs(dir: &Path, dirs: &mut Vec<PathBuf>) -> Result<()> {
+    dirs.push(dir.to_path_buf());
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            sub_dirs(&path, dirs)?;
+        }
+    }
+    Ok(())
+}
+
+/// The module a file defines, from its path relative to the source directory:
+/// `a/b.rs` and `a/b/mod.rs` both define `a::b`
+fn module_of(relative: &Path) -> String {
+    let mut parts = relative
+        .iter()
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    if let Some(file) = parts.pop() {
+        match file.strip_suffix(".rs") {
+            Some("mod") | None => {}
+            Some(stem) => parts.push(stem.into()),
+        }
+    }
+    parts.join("::")
+}
+
+/// Parse `root` and the module files it declares, recursively. The root comes
+/// first, and each file is keyed by its normalized path.
+fn follow(
+    root: Root,
+    read: &dyn Fn(&Path) -> Result<String>,
+    label: &dyn Fn(&Path) -> String,
+    cfg: &Cfg,
+    cache: &mut Cache,
+) -> Result<Vec<(PathBuf, Parsed)>> {
+    let mut files = vec![];
+    let mut seen = Set::new();
+    // Module files come with their source, read while looking for them
+    let mut queue = VecDeque::from([(root.path, root.module, None)]);
+    while let Some((path, module, source)) = queue.pop_front() {
+        let is_root = source.is_none();
+        let path = normalize(&path);
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        let source = match source {
+            Some(source) => source,
+            None => read(&path)?,
+        };
+        let mut parsed = cache.parse(&path, &source, &label(&path), &module, cfg)?;
+
+        let parent = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let stem = path.file_stem().unwrap_or_default();
+        let dirs = if ["lib", "main", "mod"].iter().any(|s| stem == *s) {
+            vec![parent.clone()]
+        } else if is_root {
+            // Files given on the command line may be crate roots of any name
+            vec![parent.join(stem), parent.clone()]
+        } else {
+            vec![parent.join(stem)]
+        };
+        for (name, file, source) in modules(&mut parsed.ast.items, &parent, &dirs, read) {
+            let module = match module.as_str() {
+                "" => name,
+                _ => format!("{module}::{name}"),
+            };
+            queue.push_back((file, module, Some(source)));
+        }
+        files.push((path, parsed));
+    }
+    Ok(files)
+}
+
+/// Find the files of the `mod name;` declarations in `items` and its inline
+/// modules, as (module path relative to `items`, normalized file, source).
+/// Declarations whose file is found are removed, so they aren't reported as
+/// skipped.
+fn modules(
+    items: &mut Vec<Item>,
+    parent: &Path,
+    dirs: &[PathBuf],
+    read: &dyn Fn(&Path) -> Result<String>,
+) -> Vec<(String, PathBuf, String)> {
+    let mut found = vec![];
+    items.retain_mut(|item| {
+        let Item::Mod(ItemMod {
+            attrs,
+            ident,
+            content,
+            ..
+        }) = item
+        else {
+            return true;
+        };
+        let name = ident.to_string();
+        let path_attr = attrs.iter().find_map(|a| match &a.meta {
+            Meta::NameValue(nv) if nv.path.is_ident("path") => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        });
+
+        if let Some((_, items)) = content {
+            let dirs = match &path_attr {
+                Some(path) => vec![parent.join(path)],
+                None => dirs.iter().map(|d| d.join(&name)).collect(),
+            };
+            let nested = modules(items, &dirs[0], &dirs, read);
+            found.extend(
+                nested
+                    .into_iter()
+                    .map(|(m, f, s)| (format!("{name}::{m}"), f, s)),
+            );
+            return true;
+        }
+
+        let candidates = match &path_attr {
+            Some(path) => vec![parent.join(path)],
+            None => dirs
+                .iter()
+                .flat_map(|d| [d.join(format!("{name}.rs")), d.join(&name).join("mod.rs")])
+                .collect(),
+        };
+        match candidates
+            .into_iter()
+            .map(|c| normalize(&c))
+            .find_map(|c| read(&c).ok().map(|source| (c, source)))
+        {
+            Some((file, source)) => {
+                found.push((name, file, source));
+                false
+            }
+            None => true,
+        }
+    });
+    found
+}
+
+fn parse(source: &str, src: &str, module: &str, cfg: &Cfg) -> Result<Parsed> {
+    let mut ast = syn::parse_file(source).map_err(|e| anyhow!("{src}: {e}"))?;
+    cfg.strip(&mut ast.items);
+    Ok(Parsed {
+        ast,
+        src: src.into(),
+        module: module.into(),
+    })
+}
+
+/// Resolve `.` and `..` without touching the file system, which may not have
+/// the file at all when reading from git
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if out.file_name().is_some() => {
+                out.pop();
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_module_of() {
+        assert_eq!(module_of(Path::new("a.rs")), "a");
+        assert_eq!(module_of(Path::new("a/b.rs")), "a::b");
+        assert_eq!(module_of(Path::new("a/b/mod.rs")), "a::b");
+    }
+
+    #[test]
+    fn test_crate() {
+        let tm = TypeMap::build_inputs(&["examples/ex21"], None, &Cfg::default()).unwrap();
+        let module = |name: &str| tm.info(name).unwrap().module.clone();
+        assert_eq!(module("App"), "");
+        assert_eq!(module("Client"), "net");
+        assert_eq!(module("Frame"), "net::proto");
+        assert_eq!(module("Store"), "store");
+        assert_eq!(module("Row"), "store::row");
+        assert_eq!(module("Legacy"), "compat");
+        assert_eq!(
+            tm.info("Frame").unwrap().span.as_ref().unwrap().file,
+            "examples/ex21/src/net/proto.rs"
+        );
+        assert!(tm
+            .edge_causes("Client", "Frame")
+            .contains(&"frames".to_string()));
+        // `impl Transport for Client` is in another file than both
+        assert_eq!(tm.info("Client").unwrap().impls, ["Transport"]);
+        let diags = tm.diagnostics();
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.contains("module `missing`"));
+
+        // Module files given alongside their crate root are only analyzed once
+        let both = TypeMap::build_inputs(
+            &["examples/ex21/src/net.rs", "examples/ex21/src/lib.rs"],
+            None,
+            &Cfg::default(),
+        )
+        .unwrap();
+        assert_eq!(both.info("Client").unwrap().module, "net");
+
+        let cfg = Cfg::enabled(["unix"]).unwrap();
+        let tm = TypeMap::build_inputs(&["examples/ex21/src/lib.rs"], None, &cfg).unwrap();
+        assert!(tm.info("Legacy").is_none());
+        assert_eq!(tm.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn test_fallback() {
+        let tm = TypeMap::build_inputs(&["examples/ex21/src/net"], None, &Cfg::default()).unwrap();
+        assert_eq!(tm.info("Frame").unwrap().module, "proto");
+
+        let tm = TypeMap::build_inputs(&["examples/ex1[56].rs"], None, &Cfg::default()).unwrap();
+        assert!(tm.info("Frame").is_some());
+        assert!(tm.info("Session").is_some());
+        assert!(TypeMap::build_inputs(&["examples/*.txt"], None, &Cfg::default()).is_err());
+
+        // Only files can be read at a revision
+        for input in ["examples/ex21", "examples/ex1*.rs"] {
+            let err = TypeMap::build_inputs(&[input], Some("HEAD"), &Cfg::default()).unwrap_err();
+            assert!(
+                err.to_string().starts_with("only files can be read"),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_generics() {
+        // Const generics, lifetime bounds, `Fn(A) -> B` and associated types
+        let tm = TypeMap::build_inputs(&["examples/ex23"], None, &Cfg::default()).unwrap();
+        assert_eq!(tm.edge_causes("View", "Buffer"), ["buffer"]);
+        assert_eq!(tm.edge_causes("View", "Render"), ["T: 'a + Render"]);
+        assert_eq!(
+            tm.edge_causes("Callback", "Report"),
+            ["F: Fn(Stage) -> Report"]
+        );
+        assert_eq!(
+            tm.edge_causes("Pipeline", "Stage"),
+            ["on_done", "I: Iterator<Item = Stage>"]
+        );
+        assert_eq!(
+            tm.edge_causes("Sink", "Render"),
+            ["I: Iterator<Item: Render>"]
+        );
+    }
+
+    #[test]
+    fn test_cache() {
+        let dir = std::env::temp_dir().join(format!("typemap-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join("lib.rs");
+        std::fs::write(&lib, "mod a;\nuse a::A;\npub struct Top {\n    a: A,\n}\n").unwrap();
+
+        let mut cache = Cache::default();
+        let inputs = [lib.to_str().unwrap()];
+        let build = |cache: &mut Cache| {
+            cache.clear_watched();
+            TypeMap::build_cached(&inputs, None, &Cfg::default(), cache).unwrap()
+        };
+        let tm = build(&mut cache);
+        assert_eq!(tm.diagnostics().len(), 1);
+        // The missing module file is watched, so creating it is noticed
+        assert!(cache.watched().contains(&dir.join("a.rs")));
+        assert!(cache.watched().contains(&dir));
+
+        std::fs::write(
+            dir.join("a.rs"),
+            "pub struct A {\n    b: B,\n}\nstruct B;\n",
+        )
+        .unwrap();
+        let tm = build(&mut cache);
+        assert!(tm.diagnostics().is_empty());
+        assert_eq!(tm.info("A").unwrap().module, "a");
+        assert_eq!(tm.edge_causes("A", "B"), ["b"]);
+        assert_eq!(cache.parsed.len(), 2);
+
+        std::fs::write(dir.join("a.rs"), "pub struct A;\n").unwrap();
+        let tm = build(&mut cache);
+        assert!(tm.info("B").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
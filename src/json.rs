@@ -0,0 +1,185 @@
+//! JSON export and import of a `TypeMap`.
+//!
+//! The document is an object with these keys:
+//!
+//! - `version`: schema version, currently `1`. Loading any other version fails.
+//! - `nodes`: one entry per user-defined type, sorted by name:
+//!   - `name`: type identifier
+//!   - `kind`: `struct`, `enum`, `union`, `type` or `trait`
+//!   - `module`: defining module path like `a::b`, empty for the crate root
+//!   - `visibility`: like `pub` or `pub(crate)`, empty for private types
+//!   - `span`: `{ "file", "line", "column" }` of the identifier, or `null`
+//!   - `members`: fields and enum variants, each `{ "variant", "name", "ty",
+//!     "deps", "inline" }` where `variant` is `null` outside enums, `name` is
+//!     the field name or tuple index (empty for unit variants), `ty` is the
+//!     type as written, `deps` the type names it mentions and `inline` those
+//!     of `deps` stored without indirection
+//!   - `generics`: type parameters in the same shape, with the bounds as `ty`
+//!   - `impls`: names of the traits implemented for the type
+//!   - `in_private_module`: whether a module around the type isn't `pub`
+//! - `edges`: `{ "from", "to", "kind", "via" }` sorted by `from`, `to`, `kind`.
+//!   `kind` is `field` for containment and `trait` for trait bounds. `via`
+//!   lists the members or bounds of `from` that cause the edge. `to` may name
+//!   a type that has no node, like `String`. Trait implementations aren't
+//!   edges; they're in the `impls` of the implementing type.
+//! - `diagnostics`: `{ "message", "span" }` for source the analysis skipped.
+//!
+//! New optional keys may be added without bumping `version`; removing or
+//! changing the meaning of a key bumps it.
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{DepGraph, Dependence, DependenceType, Diagnostic, Set, TypeInfo, TypeMap};
+
+/// Version of the JSON document written by `TypeMap::to_json`
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Document {
+    version: u32,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    #[serde(default)]
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Node {
+    name: String,
+    kind: String,
+    #[serde(flatten)]
+    info: TypeInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Edge {
+    from: String,
+    to: String,
+    kind: EdgeKind,
+    #[serde(default)]
+    via: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum EdgeKind {
+    Field,
+    Trait,
+}
+
+impl TypeMap {
+    /// Serialize to the versioned JSON schema described in this module
+    pub fn to_json(&self) -> String {
+        let mut nodes = self
+            .graph
+            .keys()
+            .map(|n| Node {
+                name: n.name(),
+                kind: n.dep_type(),
+                info: self.info(&n.name()).cloned().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let edges = self
+            .sorted_edges()
+            .into_iter()
+            .map(|(src, dest)| Edge {
+                from: src.name(),
+                to: dest.name(),
+                kind: match dest {
+                    Dependence::Field(..) => EdgeKind::Field,
+                    Dependence::Trait(..) => EdgeKind::Trait,
+                },
+                via: self.dependence_causes(&src.name(), dest),
+            })
+            .collect();
+
+        let doc = Document {
+            version: SCHEMA_VERSION,
+            nodes,
+            edges,
+            diagnostics: self.diagnostics.clone(),
+        };
+        serde_json::to_string_pretty(&doc).expect("typemaps are always serializable")
+    }
+
+    /// Load a `TypeMap` previously written by `to_json`
+    pub fn from_json(json: &str) -> Result<Self> {
+        let doc: Document = serde_json::from_str(json)?;
+        if doc.version != SCHEMA_VERSION {
+            bail!(
+                "unsupported typemap schema version {} (expected {SCHEMA_VERSION})",
+                doc.version
+            );
+        }
+
+        let mut graph = DepGraph::new();
+        let mut info = HashMap::new();
+        for node in doc.nodes {
+            let kind = DependenceType::from_ty(&node.kind)
+                .ok_or_else(|| anyhow!("unknown kind `{}` for `{}`", node.kind, node.name))?;
+            let key = match kind {
+                DependenceType::Trait => Dependence::Trait(node.name.clone(), kind),
+                _ => Dependence::Field(node.name.clone(), kind),
+            };
+            graph.insert(key, Set::new());
+            info.insert(node.name, node.info);
+        }
+
+        let keys = graph
+            .keys()
+            .map(|k| (k.name(), k.clone()))
+            .collect::<HashMap<_, _>>();
+        for edge in doc.edges {
+            let src = keys
+                .get(&edge.from)
+                .ok_or_else(|| anyhow!("edge from unknown type `{}`", edge.from))?;
+            let dest = match edge.kind {
+                EdgeKind::Field => Dependence::Field(edge.to, DependenceType::Type),
+                EdgeKind::Trait => Dependence::Trait(edge.to, DependenceType::Trait),
+            };
+            graph.get_mut(src).unwrap().insert(dest);
+        }
+
+        Ok(Self {
+            graph,
+            info,
+            diagnostics: doc.diagnostics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for ex in ["ex05", "ex06", "ex09", "ex13", "ex14"] {
+            let tm = TypeMap::build(&format!("examples/{ex}.rs")).unwrap();
+            let json = tm.to_json();
+            let loaded = TypeMap::from_json(&json).unwrap();
+            assert_eq!(loaded.graph, tm.graph);
+            assert_eq!(loaded.info, tm.info);
+            assert_eq!(loaded.to_json(), json);
+        }
+    }
+
+    #[test]
+    fn test_schema() {
+        let tm = TypeMap::build("examples/ex09.rs").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tm.to_json()).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["nodes"][1]["name"], "B");
+        assert_eq!(json["nodes"][1]["module"], "m");
+        assert_eq!(json["nodes"][1]["span"]["line"], 3);
+        assert_eq!(json["edges"][0]["to"], "B");
+        assert_eq!(json["edges"][0]["via"][0], "0");
+
+        let bad = tm.to_json().replace("\"version\": 1", "\"version\": 99");
+        assert!(TypeMap::from_json(&bad).is_err());
+    }
+}
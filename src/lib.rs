@@ -1,42 +1,57 @@
-use std::any::TypeId;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::File;
 use std::io::Read;
 
 use anyhow::Result;
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use syn::spanned::Spanned;
 use syn::*;
 
+pub mod baseline;
+pub mod cargo;
+pub mod cfg;
+pub mod cli;
+pub mod config;
+pub mod cycles;
+pub mod diff;
 pub mod dot;
+pub mod git;
+pub mod graphml;
+pub mod html;
+pub mod input;
+pub mod json;
+pub mod mermaid;
+pub mod metrics;
+pub mod plantuml;
+pub mod query;
+pub mod rules;
+#[cfg(feature = "builtin-svg")]
+pub mod svg;
+pub mod theme;
+pub mod unused;
+pub mod watch;
 
-pub type Set<T> = HashSet<T>;
+pub type Set<T> = BTreeSet<T>;
 
-pub type DepGraph = HashMap<Dependence, Set<Dependence>>;
+pub type DepGraph = BTreeMap<Dependence, Set<Dependence>>;
 
-/// A type in the analyzed codebase
-struct Ty {
-    /// Type name from std::any::type_name()
-    token_name: String,
-
-    /// Type ID from std::any::type_id()
-    id: TypeId,
-
-    /// Lexical type identifier
-    ident: String,
-}
-
-/// A dependency graph of `Ty`s
+/// A dependency graph of the types in the analyzed code
 #[derive(Debug)]
 pub struct TypeMap {
-    /// Map from a `Ty` to the `Ty`s it depends on
+    /// Map from each user-defined type to the types it depends on
     graph: DepGraph,
-    //graph: HashMap<Ty, Vec<Ty>>,
+    /// Source-level details of each user-defined type, by name
+    info: HashMap<String, TypeInfo>,
+    /// Things the analysis skipped or couldn't handle
+    diagnostics: Vec<Diagnostic>,
     // deps: HashMap<String, Dependence>,
     // Bijective map from type names to type IDs
     //resolver: BiMap<String, TypeId>,
 }
 
 // TODO: would be nice to have extra annotations within "Field/Type" (like struct/enum/fn)
-#[derive(Debug, PartialEq, Hash, Eq, Clone)]
+#[derive(Debug, PartialEq, Hash, Eq, PartialOrd, Ord, Clone)]
 pub enum DependenceType {
     Struct,
     Enum,
@@ -46,7 +61,7 @@ pub enum DependenceType {
     Temp,
 }
 
-#[derive(Debug, PartialEq, Hash, Eq, Clone)]
+#[derive(Debug, PartialEq, Hash, Eq, PartialOrd, Ord, Clone)]
 pub enum Dependence {
     Field(String, DependenceType),
     Trait(String, DependenceType),
@@ -64,24 +79,25 @@ impl Dependence {
             Self::Field(_, t) | Self::Trait(_, t) => t.to_ty(),
         }
     }
-    pub fn color(&self) -> String {
+    pub fn kind(&self) -> &DependenceType {
         match self {
-            Dependence::Trait(_, t) | Dependence::Field(_, t) => t.color(),
+            Self::Field(_, t) | Self::Trait(_, t) => t,
         }
     }
 }
 
 impl DependenceType {
-    pub fn color(&self) -> String {
-        match self {
-            Self::Struct => "red",
-            Self::Enum => "orange",
-            Self::Union => "green",
-            Self::Type => "purple",
-            Self::Trait => "blue",
-            Self::Temp => "white",
-        }
-        .into()
+    /// Inverse of `to_ty`
+    pub fn from_ty(s: &str) -> Option<Self> {
+        Some(match s {
+            "struct" => Self::Struct,
+            "enum" => Self::Enum,
+            "union" => Self::Union,
+            "type" => Self::Type,
+            "trait" => Self::Trait,
+            "temp" => Self::Temp,
+            _ => return None,
+        })
     }
     pub fn to_ty(&self) -> String {
         match self {
@@ -96,30 +112,121 @@ impl DependenceType {
     }
 }
 
-impl ToString for Dependence {
-    fn to_string(&self) -> String {
+/// A field of a user-defined type, or a variant of an enum
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Member {
+    /// The enum variant this member belongs to, if any
+    pub variant: Option<String>,
+    /// Field name, or its index for tuple fields. Empty for unit variants.
+    pub name: String,
+    /// The type as written in the source
+    pub ty: String,
+    /// Names of the types this member depends on
+    pub deps: Vec<String>,
+    /// The subset of `deps` stored inline rather than behind a pointer, which
+    /// therefore count towards the size of the containing type
+    pub inline: Vec<String>,
+}
+
+impl Member {
+    /// How the member is referred to in paths and diagrams: `b`, `0`, `Some.0`
+    pub fn label(&self) -> String {
+        match &self.variant {
+            Some(v) if self.name.is_empty() => v.clone(),
+            Some(v) => format!("{v}.{}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A location in a source file. Lines and columns start at 1.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Span {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    fn of(node: &impl Spanned, file: &str) -> Self {
+        let start = node.span().start();
+        Self {
+            file: file.into(),
+            line: start.line,
+            column: start.column + 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// Something in the source that the analysis skipped or couldn't handle
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+/// Source-level details about a user-defined type
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeInfo {
+    /// Where the type's name is defined
+    pub span: Option<Span>,
+    /// Path of the module the type is defined in, like `a::b`. Empty for the
+    /// crate root.
+    pub module: String,
+    /// Visibility as written, like `pub` or `pub(crate)`. Empty if private.
+    #[serde(default)]
+    pub visibility: String,
+    /// Fields and enum variants, in declaration order
+    pub members: Vec<Member>,
+    /// Generic type parameters, with their trait bounds as the type
+    pub generics: Vec<Member>,
+    /// Traits implemented for the type, in source order
+    #[serde(default)]
+    pub impls: Vec<String>,
+    /// Whether a module around the type isn't `pub`, so that other crates
+    /// can't name it unless it's re-exported
+    #[serde(default)]
+    pub in_private_module: bool,
+}
+
+impl std::fmt::Display for Dependence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Field(s, _) | Self::Trait(s, _) => s,
+            Self::Field(s, _) | Self::Trait(s, _) => write!(f, "{s}"),
         }
-        .into()
     }
 }
 
 impl TypeMap {
-    /// Build a `TypeMap` from a single file.
-    /// Eventually will support multi-file projects.
+    /// Build a `TypeMap` from a single file, without following its `mod`
+    /// declarations. See `build_inputs` for multi-file projects.
     pub fn build(src: &str) -> Result<Self> {
-        //let mut resolver = BiMap::new();
-
-        // Parse the file
         let mut fd = File::open(src)?;
-        let mut file = String::new();
-        fd.read_to_string(&mut file)?;
-        let file = syn::parse_file(&file)?;
-        // println!("AST:\n{:#?}", file);
+        let mut source = String::new();
+        fd.read_to_string(&mut source)?;
+        Self::from_source(&source, src)
+    }
+
+    /// Build a `TypeMap` from Rust source code. `src` is the file name used
+    /// in spans and diagnostics.
+    pub fn from_source(source: &str, src: &str) -> Result<Self> {
+        Self::from_source_with(source, src, &cfg::Cfg::default())
+    }
+
+    /// Build a `TypeMap` from parsed files, merged into one graph
+    fn from_files(files: &[input::Parsed]) -> Self {
+        //let mut resolver = BiMap::new();
+        let items = |f: &input::Parsed| f.ast.items.clone();
+        let all_items = files.iter().flat_map(items).collect::<Vec<_>>();
 
         // Find all the user-defined structs and build the dependences
-        let graph = Self::user_defined_types(&file)
+        let graph = Self::user_defined_types(&all_items)
             .into_iter()
             .map(|(type_name, s, g)| {
                 let field_deps = Set::from_iter(
@@ -134,27 +241,20 @@ impl TypeMap {
                                                   // .collect::<Vec<Dependence>>(),
                 );
 
-                let generic_names = Set::from_iter(
-                    Self::generic_names(&g)
-                        .into_iter()
-                        .map(|n| Dependence::Field(n, DependenceType::Temp))
-                        .collect::<Vec<Dependence>>(),
-                );
+                let generic_names = Set::from_iter(Self::generic_names(&g));
 
                 // deps = (field_deps \ generic_names) U generic_deps
                 let deps = field_deps
-                    .difference(&generic_names)
-                    .cloned()
-                    .collect::<Set<Dependence>>()
-                    .union(&generic_deps)
-                    .cloned()
+                    .into_iter()
+                    .filter(|d| !generic_names.contains(&d.name()))
+                    .chain(generic_deps)
                     .collect();
 
                 (type_name, deps)
             })
             .collect::<DepGraph>();
         /*
-        let mut deps: HashSet<Dependence> = graph
+        let mut deps: Set<Dependence> = graph
             .clone()
             .values()
             .flatten()
@@ -166,60 +266,450 @@ impl TypeMap {
         dbg!(&deps);
         */
 
-        Ok(Self { graph })
+        let mut info = files
+            .iter()
+            .flat_map(|f| Self::type_info(&f.ast.items, &f.module, &f.src))
+            .collect::<HashMap<_, _>>();
+
+        // Trait implementations aren't dependences, they're only kept to be
+        // drawn as realizations in class diagrams
+        for (ty, tr) in Self::trait_impls(&all_items) {
+            if let Some(info) = info.get_mut(&ty) {
+                if !info.impls.contains(&tr) {
+                    info.impls.push(tr);
+                }
+            }
+        }
+
+        // Other crates can't name types in private modules, even `pub` ones
+        let private = files
+            .iter()
+            .flat_map(|f| Self::private_modules(&f.ast.items, &f.module))
+            .collect::<Vec<_>>();
+        for i in info.values_mut() {
+            i.in_private_module = private
+                .iter()
+                .any(|m| i.module == *m || i.module.starts_with(&format!("{m}::")));
+        }
+
+        // `Self` and qualified paths like `crate::m::B` name user-defined
+        // types, so they're resolved once for every consumer to see the same
+        // nodes
+        let modules = info
+            .iter()
+            .map(|(name, i)| (name.clone(), i.module.clone()))
+            .collect::<HashMap<_, _>>();
+        let resolve = |src: &str, name: &String| match name.as_str() {
+            "Self" => src.to_string(),
+            _ => Self::resolve(&modules, name),
+        };
+        let graph = graph
+            .into_iter()
+            .map(|(src, deps)| {
+                let name = src.name();
+                let deps = deps
+                    .into_iter()
+                    .map(|d| match d {
+                        Dependence::Field(n, t) => Dependence::Field(resolve(&name, &n), t),
+                        Dependence::Trait(n, t) => Dependence::Trait(resolve(&name, &n), t),
+                    })
+                    .collect();
+                (src, deps)
+            })
+            .collect();
+        for (name, i) in info.iter_mut() {
+            for m in i.members.iter_mut().chain(&mut i.generics) {
+                m.deps = m.deps.iter().map(|d| resolve(name, d)).collect();
+                m.inline = m.inline.iter().map(|d| resolve(name, d)).collect();
+            }
+        }
+
+        let diagnostics = files
+            .iter()
+            .flat_map(|f| Self::skipped_items(&f.ast.items, &f.src))
+            .collect();
+
+        Self {
+            graph,
+            info,
+            diagnostics,
+        }
+    }
+
+    /// The user-defined type a qualified path like `m::B` or `crate::m::B`
+    /// names, given the module of each type, or `name` itself if it names none
+    fn resolve(modules: &HashMap<String, String>, name: &str) -> String {
+        let Some((prefix, last)) = name.rsplit_once("::") else {
+            return name.into();
+        };
+        let path = prefix
+            .split("::")
+            .filter(|m| !matches!(*m, "crate" | "self" | "super"))
+            .collect::<Vec<_>>()
+            .join("::");
+        match modules.get(last) {
+            Some(module)
+                if path.is_empty() || *module == path || module.ends_with(&format!("::{path}")) =>
+            {
+                last.into()
+            }
+            _ => name.into(),
+        }
     }
 
     pub fn graph(&self) -> &DepGraph {
         &self.graph
     }
 
+    /// Fields and generics of a user-defined type
+    pub fn info(&self, name: &str) -> Option<&TypeInfo> {
+        self.info.get(name)
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Report items that may define types but weren't looked into
+    fn skipped_items(items: &[Item], file: &str) -> Vec<Diagnostic> {
+        items
+            .iter()
+            .flat_map(|item| match item {
+                Item::Mod(ItemMod {
+                    content: Some((_, items)),
+                    ..
+                }) => Self::skipped_items(items, file),
+                Item::Mod(m) => vec![Diagnostic {
+                    message: format!(
+                        "module `{}` is in another file and was not analyzed",
+                        m.ident
+                    ),
+                    span: Some(Span::of(m, file)),
+                }],
+                Item::Macro(m) => vec![Diagnostic {
+                    message: format!(
+                        "types defined by macro `{}!` were not analyzed",
+                        Self::tokens_string(&m.mac.path)
+                    ),
+                    span: Some(Span::of(m, file)),
+                }],
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    /// The fields and generic bounds of `src` that make it depend on `dest`
+    pub fn edge_causes(&self, src: &str, dest: &str) -> Vec<String> {
+        [
+            Dependence::Field(dest.into(), DependenceType::Type),
+            Dependence::Trait(dest.into(), DependenceType::Trait),
+        ]
+        .iter()
+        .flat_map(|d| self.dependence_causes(src, d))
+        .collect()
+    }
+
+    /// Like `edge_causes`, for a single kind of dependence: field labels for
+    /// `Field` and `T: Bound` for `Trait`
+    pub fn dependence_causes(&self, src: &str, dest: &Dependence) -> Vec<String> {
+        let Some(info) = self.info(src) else {
+            return vec![];
+        };
+        let name = dest.name();
+        match dest {
+            Dependence::Field(..) => info
+                .members
+                .iter()
+                .filter(|m| m.deps.contains(&name))
+                .map(Member::label)
+                .collect(),
+            Dependence::Trait(..) => info
+                .generics
+                .iter()
+                .filter(|g| g.deps.contains(&name))
+                .map(|g| format!("{}: {}", g.name, g.ty))
+                .collect(),
+        }
+    }
+
+    /// Every edge, sorted by source name, then destination name and kind
+    pub fn sorted_edges(&self) -> Vec<(&Dependence, &Dependence)> {
+        let mut edges = self
+            .graph
+            .iter()
+            .flat_map(|(src, dests)| dests.iter().map(move |d| (src, d)))
+            .collect::<Vec<_>>();
+        edges.sort_by_key(|(src, dest)| (src.name(), dest.name(), *dest));
+        edges
+    }
+
+    /// (type, trait) for each trait implemented by a type of the graph, sorted.
+    /// Implementations don't make a type depend on the trait, so they're only
+    /// drawn as realizations in class diagrams.
+    pub fn realizations(&self) -> Vec<(String, String)> {
+        let mut impls = self
+            .graph
+            .keys()
+            .filter_map(|k| self.info(&k.name()).map(|info| (k.name(), info)))
+            .flat_map(|(ty, info)| info.impls.iter().map(move |tr| (ty.clone(), tr.clone())))
+            .collect::<Vec<_>>();
+        impls.sort();
+        impls
+    }
+
+    /// (type, trait) names of every `impl Trait for Type` block
+    fn trait_impls(items: &[Item]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .flat_map(|item| match item {
+                Item::Impl(ItemImpl {
+                    trait_: Some((None, tr, _)),
+                    self_ty,
+                    ..
+                }) => match &**self_ty {
+                    Type::Path(TypePath { path, .. }) => {
+                        let ty = path.segments.last().unwrap().ident.to_string();
+                        let tr = tr
+                            .segments
+                            .iter()
+                            .map(|seg| seg.ident.to_string())
+                            .collect::<Vec<String>>()
+                            .join("::");
+                        vec![(ty, tr)]
+                    }
+                    _ => vec![],
+                },
+                Item::Mod(ItemMod {
+                    content: Some((_, items)),
+                    ..
+                }) => Self::trait_impls(items),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    /// Paths of the modules declared in `module` and its inline submodules
+    /// that aren't `pub`
+    fn private_modules(items: &[Item], module: &str) -> Vec<String> {
+        items
+            .iter()
+            .flat_map(|item| {
+                let Item::Mod(m) = item else {
+                    return vec![];
+                };
+                let path = match module {
+                    "" => m.ident.to_string(),
+                    _ => format!("{module}::{}", m.ident),
+                };
+                let inner = match &m.content {
+                    Some((_, items)) => Self::private_modules(items, &path),
+                    None => vec![],
+                };
+                let private = Self::tokens_string(&m.vis) != "pub";
+                private.then_some(path).into_iter().chain(inner).collect()
+            })
+            .collect()
+    }
+
+    /// Collect the members and generics of each user-defined type in `module`
+    /// and its inline submodules
+    fn type_info(items: &[Item], module: &str, file: &str) -> HashMap<String, TypeInfo> {
+        items
+            .iter()
+            .flat_map(|item| {
+                let (ident, vis, members, generics) = match item {
+                    Item::Struct(s) => (
+                        &s.ident,
+                        &s.vis,
+                        Self::members(None, &s.fields),
+                        &s.generics,
+                    ),
+                    Item::Enum(e) => (
+                        &e.ident,
+                        &e.vis,
+                        e.variants
+                            .iter()
+                            .flat_map(|v| {
+                                let variant = Some(v.ident.to_string());
+                                match &v.fields {
+                                    Fields::Unit => vec![Member {
+                                        variant,
+                                        name: String::new(),
+                                        ty: String::new(),
+                                        deps: vec![],
+                                        inline: vec![],
+                                    }],
+                                    fields => Self::members(variant, fields),
+                                }
+                            })
+                            .collect(),
+                        &e.generics,
+                    ),
+                    Item::Union(u) => (
+                        &u.ident,
+                        &u.vis,
+                        Self::members(None, &Fields::Named(u.fields.clone())),
+                        &u.generics,
+                    ),
+                    Item::Type(t) => (&t.ident, &t.vis, vec![], &t.generics),
+                    Item::Trait(t) => (&t.ident, &t.vis, vec![], &t.generics),
+                    Item::Mod(ItemMod {
+                        ident,
+                        content: Some((_, items)),
+                        ..
+                    }) => {
+                        let module = match module {
+                            "" => ident.to_string(),
+                            _ => format!("{module}::{ident}"),
+                        };
+                        return Self::type_info(items, &module, file);
+                    }
+                    _ => return HashMap::new(),
+                };
+
+                // Generic parameters are not dependences of their own
+                let generic_names =
+                    Set::from_iter(Self::generic_names(std::slice::from_ref(generics)));
+                let members = members
+                    .into_iter()
+                    .map(|mut m| {
+                        m.deps.retain(|d| !generic_names.contains(d));
+                        m.inline.retain(|d| !generic_names.contains(d));
+                        m
+                    })
+                    .collect();
+
+                let generics = generics
+                    .type_params()
+                    .map(|t| Member {
+                        variant: None,
+                        name: t.ident.to_string(),
+                        ty: Self::tokens_string(&t.bounds),
+                        deps: t
+                            .bounds
+                            .iter()
+                            .flat_map(|b| match b {
+                                TypeParamBound::Trait(TraitBound { path, .. }) => {
+                                    Self::types_from_path(path)
+                                }
+                                _ => vec![],
+                            })
+                            .collect(),
+                        inline: vec![],
+                    })
+                    .collect();
+
+                let info = TypeInfo {
+                    span: Some(Span::of(ident, file)),
+                    module: module.into(),
+                    visibility: Self::tokens_string(vis),
+                    members,
+                    generics,
+                    impls: vec![],
+                    in_private_module: false,
+                };
+                HashMap::from([(ident.to_string(), info)])
+            })
+            .collect()
+    }
+
+    /// The fields of a struct, union or enum variant as `Member`s
+    fn members(variant: Option<String>, fields: &Fields) -> Vec<Member> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| Member {
+                variant: variant.clone(),
+                name: field
+                    .ident
+                    .as_ref()
+                    .map_or_else(|| i.to_string(), |id| id.to_string()),
+                ty: Self::tokens_string(&field.ty),
+                deps: Self::base_types(&field.ty),
+                inline: Self::inline_types(&field.ty),
+            })
+            .collect()
+    }
+
+    /// Render a syntax tree node back into (roughly) how it was written
+    fn tokens_string(tokens: &impl ToTokens) -> String {
+        let mut s = tokens.to_token_stream().to_string();
+        for (from, to) in [
+            (" :: ", "::"),
+            (":: ", "::"),
+            (" <", "<"),
+            ("< ", "<"),
+            (" >", ">"),
+            (" ,", ","),
+            (" ;", ";"),
+            ("& ", "&"),
+            ("( ", "("),
+            (" )", ")"),
+            ("[ ", "["),
+            (" ]", "]"),
+            (" : ", ": "),
+            ("fn (", "fn("),
+            ("Fn (", "Fn("),
+            ("FnMut (", "FnMut("),
+            ("FnOnce (", "FnOnce("),
+            ("pub (", "pub("),
+        ] {
+            s = s.replace(from, to);
+        }
+        s
+    }
+
     /// Return a list of pairs of user defined type identifier with their
-    /// fields/generics.
-    fn user_defined_types(file: &syn::File) -> Vec<(Dependence, Vec<Fields>, Vec<Generics>)> {
-        file.items
-            .clone()
-            .into_iter()
-            .map(|item| match item {
-                Item::Struct(s) => (
+    /// fields/generics, including those in inline modules.
+    fn user_defined_types(items: &[Item]) -> Vec<(Dependence, Vec<Fields>, Vec<Generics>)> {
+        items
+            .iter()
+            .cloned()
+            .flat_map(|item| match item {
+                Item::Struct(s) => vec![(
                     Dependence::Field(s.ident.to_string(), DependenceType::Struct),
                     vec![s.fields],
                     vec![s.generics],
-                ),
-                Item::Enum(e) => (
+                )],
+                Item::Enum(e) => vec![(
                     Dependence::Field(e.ident.to_string(), DependenceType::Enum),
                     e.variants
                         .into_iter()
                         .map(|v| v.fields)
                         .collect::<Vec<Fields>>(),
                     vec![e.generics],
-                ),
-                Item::Union(u) => (
+                )],
+                Item::Union(u) => vec![(
                     Dependence::Field(u.ident.to_string(), DependenceType::Union),
                     vec![Fields::Named(u.fields)],
                     vec![u.generics],
-                ),
+                )],
                 // TODO: Also need to add supertrait support
-                Item::Type(t) => (
+                Item::Type(t) => vec![(
                     Dependence::Field(t.ident.to_string(), DependenceType::Type),
                     vec![],
                     vec![t.generics],
-                ),
-                Item::Trait(t) => (
+                )],
+                Item::Trait(t) => vec![(
                     Dependence::Trait(t.ident.to_string(), DependenceType::Trait),
                     vec![],
                     vec![t.generics],
-                ),
-                // Item::Mod(m) => Self::user_defined_types(...)
-                _ => todo!(),
+                )],
+                Item::Mod(ItemMod {
+                    content: Some((_, items)),
+                    ..
+                }) => Self::user_defined_types(&items),
+                // Functions, impls, imports, etc. don't define types
+                _ => vec![],
             })
             .collect::<Vec<(Dependence, Vec<Fields>, Vec<Generics>)>>()
     }
 
     /// Return all the type identifiers that these fields depend on
     // TODO: move the `Dependence` wrapper type in here
-    fn field_dependents(fields: &Vec<Fields>) -> Vec<Dependence> {
+    fn field_dependents(fields: &[Fields]) -> Vec<Dependence> {
         fields
-            .into_iter()
+            .iter()
             .flat_map(|f| match f {
                 Fields::Unit => Vec::new(),
                 Fields::Named(FieldsNamed { named: fields, .. }) => fields
@@ -239,9 +729,9 @@ impl TypeMap {
     }
 
     /// Get the trait bounds on any generic parameters, which form a (trait) dependence.
-    fn generic_dependents(generics: &Vec<Generics>) -> Vec<Dependence> {
+    fn generic_dependents(generics: &[Generics]) -> Vec<Dependence> {
         generics
-            .into_iter()
+            .iter()
             .flat_map(|g| {
                 {
                     g.params
@@ -260,13 +750,15 @@ impl TypeMap {
                                             .map(|d| Dependence::Trait(d, DependenceType::Trait))
                                             .collect::<Vec<Dependence>>()
                                     }
-                                    _ => todo!(),
+                                    // Lifetime bounds like `T: 'a`
+                                    _ => vec![],
                                 })
                                 .collect::<Vec<Dependence>>(),
                             GenericParam::Lifetime(_) => {
                                 vec![]
                             }
-                            GenericParam::Const(_) => todo!("const generics not yet supported"),
+                            // The type of a const parameter is a primitive
+                            GenericParam::Const(_) => vec![],
                         })
                         .collect::<Vec<Dependence>>()
                 }
@@ -275,9 +767,9 @@ impl TypeMap {
     }
 
     /// The generic parameter names (without type bounds)
-    fn generic_names(generics: &Vec<Generics>) -> Vec<String> {
+    fn generic_names(generics: &[Generics]) -> Vec<String> {
         generics
-            .into_iter()
+            .iter()
             .flat_map(|g| {
                 {
                     g.params
@@ -286,7 +778,7 @@ impl TypeMap {
                         .map(|param| match param {
                             GenericParam::Type(t) => t.ident.to_string(),
                             GenericParam::Lifetime(_) => "".into(),
-                            GenericParam::Const(_) => todo!("const generics not yet supported"),
+                            GenericParam::Const(c) => c.ident.to_string(),
                         })
                         .collect::<Vec<String>>()
                 }
@@ -309,17 +801,38 @@ impl TypeMap {
                 PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) => {
                     args.into_iter()
                         .flat_map(|arg| match arg {
-                            // GenericArgument::Lifetime(_) => todo!(),
-                            GenericArgument::Type(ty) => Self::base_types(&ty),
-                            // GenericArgument::Const(_) => todo!(),
-                            GenericArgument::AssocType(_) => todo!(),
-                            // GenericArgument::AssocConst(_) => todo!(),
-                            GenericArgument::Constraint(_) => todo!(),
-                            _ => vec![], // TODO: handle these
+                            GenericArgument::Type(ty) => Self::base_types(ty),
+                            // `Iterator<Item = X>`
+                            GenericArgument::AssocType(assoc) => Self::base_types(&assoc.ty),
+                            // `Iterator<Item: Trait>`
+                            GenericArgument::Constraint(constraint) => constraint
+                                .bounds
+                                .iter()
+                                .flat_map(|b| match b {
+                                    TypeParamBound::Trait(t) => Self::types_from_path(&t.path),
+                                    _ => vec![],
+                                })
+                                .collect(),
+                            // Lifetimes and consts
+                            _ => vec![],
                         })
                         .collect::<Vec<String>>()
                 }
-                PathArguments::Parenthesized(_) => todo!(),
+                // `Fn(A) -> B`
+                PathArguments::Parenthesized(ParenthesizedGenericArguments {
+                    inputs,
+                    output,
+                    ..
+                }) => {
+                    let mut tys = inputs
+                        .iter()
+                        .flat_map(Self::base_types)
+                        .collect::<Vec<String>>();
+                    if let ReturnType::Type(_, ty) = output {
+                        tys.extend(Self::base_types(ty));
+                    }
+                    tys
+                }
             })
             .collect::<Vec<String>>();
         args.push(base);
@@ -345,7 +858,7 @@ impl TypeMap {
             }
             Type::Tuple(TypeTuple { elems, .. }) => elems
                 .into_iter()
-                .flat_map(|i| Self::base_types(i))
+                .flat_map(Self::base_types)
                 .collect::<Vec<String>>(),
             Type::Slice(TypeSlice { elem, .. }) => Self::base_types(elem),
             Type::ImplTrait(TypeImplTrait { bounds, .. }) => {
@@ -362,6 +875,67 @@ impl TypeMap {
             _ => vec![],
         }
     }
+
+    /// The types a value of type `ty` contains directly. Generic arguments of
+    /// pointer-like and heap-allocating std types are behind an indirection;
+    /// those of every other type are assumed to be stored inline, as with
+    /// `Option<T>` or `Cell<T>`.
+    fn inline_types(ty: &Type) -> Vec<String> {
+        const INDIRECT: [&str; 13] = [
+            "Box",
+            "Rc",
+            "Arc",
+            "Weak",
+            "Vec",
+            "VecDeque",
+            "LinkedList",
+            "BinaryHeap",
+            "HashMap",
+            "HashSet",
+            "BTreeMap",
+            "BTreeSet",
+            "PhantomData",
+        ];
+        match ty {
+            Type::Path(TypePath { path, .. }) => {
+                let base = path
+                    .segments
+                    .iter()
+                    .map(|seg| seg.ident.to_string())
+                    .collect::<Vec<String>>()
+                    .join("::");
+                let mut tys = match path.segments.last() {
+                    Some(seg) if !INDIRECT.contains(&seg.ident.to_string().as_str()) => {
+                        match &seg.arguments {
+                            PathArguments::AngleBracketed(AngleBracketedGenericArguments {
+                                args,
+                                ..
+                            }) => args
+                                .iter()
+                                .flat_map(|arg| match arg {
+                                    GenericArgument::Type(ty) => Self::inline_types(ty),
+                                    _ => vec![],
+                                })
+                                .collect(),
+                            _ => vec![],
+                        }
+                    }
+                    _ => vec![],
+                };
+                tys.push(base);
+                tys
+            }
+            Type::Array(TypeArray { elem, .. }) => Self::inline_types(elem),
+            Type::Tuple(TypeTuple { elems, .. }) => {
+                elems.iter().flat_map(Self::inline_types).collect()
+            }
+            Type::Paren(TypeParen { elem, .. }) | Type::Group(TypeGroup { elem, .. }) => {
+                Self::inline_types(elem)
+            }
+            // References, pointers, slices, functions and trait objects
+            _ => vec![],
+        }
+    }
 }
 
 /*
@@ -0,0 +1,117 @@
+//! Mermaid class diagram output, for markdown renderers that don't support DOT.
+use super::{Dependence, DependenceType, TypeMap};
+
+/// Render a `TypeMap` as a Mermaid `classDiagram`. Fields and enum variants
+/// become class members, traits are `<<interface>>`s, trait implementations
+/// are realizations and fields are compositions.
+pub fn generate_mermaid(typemap: &TypeMap) -> String {
+    let mut out = String::from("classDiagram\n");
+
+    let mut keys = typemap.graph().keys().collect::<Vec<_>>();
+    keys.sort_by_key(|k| k.name());
+    for key in &keys {
+        let name = key.name();
+        out += &format!("    class {}", class_decl(&name));
+        let (Dependence::Field(_, kind) | Dependence::Trait(_, kind)) = key;
+        out += &format!(" {{\n        <<{}>>\n", annotation(kind));
+        for m in typemap.info(&name).map(|i| &i.members[..]).unwrap_or(&[]) {
+            if m.ty.is_empty() {
+                out += &format!("        {}\n", m.label());
+            } else {
+                out += &format!("        {} {}\n", generic_ty(&m.ty), m.label());
+            }
+        }
+        out += "    }\n";
+    }
+
+    // Types that are only depended on need a declaration when their name
+    // isn't a valid class identifier
+    let realizations = typemap.realizations();
+    let mut others = typemap
+        .nodes()
+        .into_iter()
+        .chain(realizations.iter().map(|(_, tr)| tr.clone()))
+        .filter(|n| !keys.iter().any(|k| k.name() == *n) && id(n) != *n)
+        .collect::<Vec<_>>();
+    others.sort();
+    others.dedup();
+    for n in others {
+        out += &format!("    class {}\n", class_decl(&n));
+    }
+
+    for (src, dest) in typemap.sorted_edges() {
+        let arrow = match dest {
+            Dependence::Field(..) => "*--",
+            Dependence::Trait(..) => "..>",
+        };
+        out += &format!("    {} {arrow} {}", id(&src.name()), id(&dest.name()));
+        let label = typemap.dependence_causes(&src.name(), dest);
+        if label.is_empty() {
+            out += "\n";
+        } else {
+            out += &format!(" : {}\n", label.join(", "));
+        }
+    }
+    for (ty, tr) in realizations {
+        out += &format!("    {} ..|> {}\n", id(&ty), id(&tr));
+    }
+    out
+}
+
+fn annotation(kind: &DependenceType) -> &'static str {
+    match kind {
+        DependenceType::Trait => "interface",
+        DependenceType::Enum => "enumeration",
+        DependenceType::Union => "union",
+        DependenceType::Type => "alias",
+        DependenceType::Struct | DependenceType::Temp => "struct",
+    }
+}
+
+/// A class identifier: Mermaid only accepts word characters
+fn id(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Class identifier, with the real name as a label if it had to be mangled
+fn class_decl(name: &str) -> String {
+    match id(name) {
+        id if id == name => id,
+        id => format!("{id}[\"{name}\"]"),
+    }
+}
+
+/// Mermaid writes generics as `Vec~T~` since `<` and `>` are reserved
+fn generic_ty(ty: &str) -> String {
+    ty.replace(['<', '>'], "~")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_class_diagram() {
+        let tm = TypeMap::build("examples/ex14.rs").unwrap();
+        let mermaid = generate_mermaid(&tm);
+        assert!(mermaid.starts_with("classDiagram\n"));
+        assert!(mermaid.contains("    class Shape {\n        <<interface>>\n    }\n"));
+        assert!(mermaid.contains("        Vec~S~ shapes\n"));
+        assert!(mermaid
+            .contains("        Point Square.corner\n        f64 Square.side\n        Empty\n"));
+        assert!(mermaid.contains("    Circle ..|> Shape\n"));
+        // Implementing a trait doesn't depend on it
+        assert!(!tm.dependencies("Circle", None).unwrap().contains("Shape"));
+        assert!(tm.edge_causes("Circle", "Shape").is_empty());
+        assert!(mermaid.contains("    Canvas *-- Point : origin\n"));
+        assert!(mermaid.contains("    Canvas ..> Shape : S: Shape\n"));
+
+        // Qualified paths lead to the type they name
+        let tm = TypeMap::build("examples/ex09.rs").unwrap();
+        let mermaid = generate_mermaid(&tm);
+        assert!(mermaid.contains("    A *-- B : 0\n"));
+        assert!(!mermaid.contains("m__B"));
+    }
+}
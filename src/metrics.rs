@@ -0,0 +1,297 @@
+//! Coupling metrics for types and modules.
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{Set, TypeMap};
+use crate::query::Direction;
+
+/// Coupling metrics of a single user-defined type
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypeMetrics {
+    pub name: String,
+    /// Module the type is defined in; empty for the crate root
+    pub module: String,
+    /// Number of types that depend on this one (afferent coupling, Ca)
+    pub fan_in: usize,
+    /// Number of types this one depends on (efferent coupling, Ce)
+    pub fan_out: usize,
+    /// Level in the condensed DAG; 0 if the type has no dependencies
+    pub depth: usize,
+    /// Number of types this one transitively depends on
+    pub closure: usize,
+    /// Ce / (Ca + Ce): 0 is maximally stable, 1 maximally unstable
+    pub instability: f64,
+}
+
+/// Coupling metrics of a module, counting only dependences that cross its boundary
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModuleMetrics {
+    /// Module path; empty for the crate root
+    pub module: String,
+    /// Number of types defined in the module
+    pub types: usize,
+    /// Number of types outside the module that depend on types inside it
+    pub fan_in: usize,
+    /// Number of types outside the module that types inside it depend on
+    pub fan_out: usize,
+    /// Deepest level of any type in the module
+    pub depth: usize,
+    pub instability: f64,
+}
+
+/// Metrics for every user-defined type and module in a `TypeMap`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricsReport {
+    pub types: Vec<TypeMetrics>,
+    pub modules: Vec<ModuleMetrics>,
+}
+
+fn instability(fan_in: usize, fan_out: usize) -> f64 {
+    if fan_in + fan_out == 0 {
+        0.0
+    } else {
+        fan_out as f64 / (fan_in + fan_out) as f64
+    }
+}
+
+impl TypeMap {
+    /// Compute coupling metrics for each user-defined type and module
+    pub fn metrics(&self) -> MetricsReport {
+        let deps = self.adjacency(Direction::Dependencies);
+        let dependents = self.adjacency(Direction::Dependents);
+        let cond = self.condensation();
+        let module_of = |name: &str| self.info(name).map(|i| i.module.clone());
+
+        let mut names = self.graph.keys().map(|n| n.name()).collect::<Vec<_>>();
+        names.sort();
+        let types = names
+            .iter()
+            .map(|name| {
+                let fan_in = dependents[name].len();
+                let fan_out = deps[name].len();
+                TypeMetrics {
+                    name: name.clone(),
+                    module: module_of(name).unwrap_or_default(),
+                    fan_in,
+                    fan_out,
+                    depth: cond.levels[cond.component_of(name).unwrap()],
+                    closure: self.dependencies(name, None).map_or(0, |d| d.len() - 1),
+                    instability: instability(fan_in, fan_out),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut by_module: BTreeMap<&str, Vec<&TypeMetrics>> = BTreeMap::new();
+        for t in &types {
+            by_module.entry(&t.module).or_default().push(t);
+        }
+        let modules = by_module
+            .into_iter()
+            .map(|(module, members)| {
+                let outside = |n: &String| module_of(n).as_deref() != Some(module);
+                let fan_in = members
+                    .iter()
+                    .flat_map(|t| dependents[&t.name].iter().filter(|n| outside(n)))
+                    .collect::<Set<_>>()
+                    .len();
+                let fan_out = members
+                    .iter()
+                    .flat_map(|t| deps[&t.name].iter().filter(|n| outside(n)))
+                    .collect::<Set<_>>()
+                    .len();
+                ModuleMetrics {
+                    module: module.into(),
+                    types: members.len(),
+                    fan_in,
+                    fan_out,
+                    depth: members.iter().map(|t| t.depth).max().unwrap_or(0),
+                    instability: instability(fan_in, fan_out),
+                }
+            })
+            .collect();
+
+        MetricsReport { types, modules }
+    }
+}
+
+impl MetricsReport {
+    /// Render as aligned plain-text tables
+    pub fn to_table(&self) -> String {
+        let types = self
+            .types
+            .iter()
+            .map(|t| {
+                vec![
+                    t.name.clone(),
+                    display_module(&t.module),
+                    t.fan_in.to_string(),
+                    t.fan_out.to_string(),
+                    t.depth.to_string(),
+                    t.closure.to_string(),
+                    format!("{:.2}", t.instability),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let modules = self
+            .modules
+            .iter()
+            .map(|m| {
+                vec![
+                    display_module(&m.module),
+                    m.types.to_string(),
+                    m.fan_in.to_string(),
+                    m.fan_out.to_string(),
+                    m.depth.to_string(),
+                    format!("{:.2}", m.instability),
+                ]
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{}\n{}",
+            table(
+                &[
+                    "type",
+                    "module",
+                    "fan-in",
+                    "fan-out",
+                    "depth",
+                    "closure",
+                    "instability"
+                ],
+                &types
+            ),
+            table(
+                &[
+                    "module",
+                    "types",
+                    "fan-in",
+                    "fan-out",
+                    "depth",
+                    "instability"
+                ],
+                &modules
+            )
+        )
+    }
+
+    /// Render as CSV: the per-type table, then the per-module one, each with
+    /// its own header and separated by an empty line
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("name,module,fan_in,fan_out,depth,closure,instability\n");
+        for t in &self.types {
+            out += &format!(
+                "{},{},{},{},{},{},{:.4}\n",
+                csv_field(&t.name),
+                csv_field(&t.module),
+                t.fan_in,
+                t.fan_out,
+                t.depth,
+                t.closure,
+                t.instability
+            );
+        }
+        out += "\nmodule,types,fan_in,fan_out,depth,instability\n";
+        for m in &self.modules {
+            out += &format!(
+                "{},{},{},{},{},{:.4}\n",
+                csv_field(&m.module),
+                m.types,
+                m.fan_in,
+                m.fan_out,
+                m.depth,
+                m.instability
+            );
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("metrics are always serializable")
+    }
+}
+
+fn display_module(module: &str) -> String {
+    match module {
+        "" => "crate".into(),
+        m => m.into(),
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.into()
+    }
+}
+
+/// Left-align `rows` under `header`
+fn table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let widths = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|r| r[i].len())
+                .chain([header[i].len()])
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{c:w$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+            + "\n"
+    };
+    let mut out = line(header.to_vec());
+    for row in rows {
+        out += &line(row.iter().map(String::as_str).collect());
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_type_metrics() {
+        let tm = TypeMap::build("examples/ex05.rs").unwrap();
+        let report = tm.metrics();
+        let c = report.types.iter().find(|t| t.name == "C").unwrap();
+        assert_eq!((c.fan_in, c.fan_out, c.depth, c.closure), (1, 1, 2, 3));
+        assert_eq!(c.instability, 0.5);
+        let b = report.types.iter().find(|t| t.name == "B").unwrap();
+        assert_eq!((b.fan_in, b.fan_out, b.instability), (1, 0, 0.0));
+    }
+
+    #[test]
+    fn test_module_metrics() {
+        let tm = TypeMap::build("examples/ex09.rs").unwrap();
+        let report = tm.metrics();
+        assert_eq!(report.modules.len(), 2);
+        assert_eq!(report.modules[0].module, "");
+        assert_eq!(report.modules[0].fan_out, 1);
+        assert_eq!(report.modules[1].module, "m");
+        assert_eq!(report.modules[1].types, 1);
+        // `A` depends on `m::B`, which is `B` in `m`
+        assert_eq!(report.modules[1].fan_in, 1);
+        let b = report.types.iter().find(|t| t.name == "B").unwrap();
+        assert_eq!((b.module.as_str(), b.fan_in), ("m", 1));
+        assert_eq!(
+            report.to_csv(),
+            "name,module,fan_in,fan_out,depth,closure,instability\n\
+             A,,0,1,1,1,1.0000\n\
+             B,m,1,0,0,0,0.0000\n\
+             \n\
+             module,types,fan_in,fan_out,depth,instability\n\
+             ,1,0,1,1,1.0000\n\
+             m,1,1,0,0,0.0000\n"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! PlantUML class diagram output.
+use std::collections::BTreeMap;
+
+use super::{Dependence, DependenceType, Member, TypeMap};
+
+/// Types of a module and its submodules
+#[derive(Default)]
+struct Package<'a> {
+    types: Vec<&'a Dependence>,
+    children: BTreeMap<String, Package<'a>>,
+}
+
+/// Render a `TypeMap` as a PlantUML class diagram, with a package per module
+pub fn generate_plantuml(typemap: &TypeMap) -> String {
+    let mut root = Package::default();
+    let mut keys = typemap.graph().keys().collect::<Vec<_>>();
+    keys.sort_by_key(|k| k.name());
+    for key in keys {
+        let module = typemap
+            .info(&key.name())
+            .map(|i| i.module.clone())
+            .unwrap_or_default();
+        let package = module
+            .split("::")
+            .filter(|m| !m.is_empty())
+            .fold(&mut root, |p, m| p.children.entry(m.into()).or_default());
+        package.types.push(key);
+    }
+
+    let mut out = String::from("@startuml\nset separator none\n");
+    write_package(typemap, &root, 0, &mut out);
+    for (src, dest) in typemap.sorted_edges() {
+        let arrow = match dest {
+            Dependence::Field(..) => "*--",
+            Dependence::Trait(..) => "..>",
+        };
+        out += &format!("{} {arrow} {}", id(&src.name()), id(&dest.name()));
+        let label = typemap.dependence_causes(&src.name(), dest);
+        if label.is_empty() {
+            out += "\n";
+        } else {
+            out += &format!(" : {}\n", label.join(", "));
+        }
+    }
+    for (ty, tr) in typemap.realizations() {
+        out += &format!("{} ..|> {}\n", id(&ty), id(&tr));
+    }
+    out + "@enduml\n"
+}
+
+fn write_package(typemap: &TypeMap, package: &Package, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    for key in &package.types {
+        let name = key.name();
+        let (Dependence::Field(_, kind) | Dependence::Trait(_, kind)) = key;
+        let decl = match kind {
+            DependenceType::Trait => "interface",
+            DependenceType::Enum => "enum",
+            DependenceType::Union | DependenceType::Type => "class",
+            DependenceType::Struct | DependenceType::Temp => "struct",
+        };
+        let stereotype = match kind {
+            DependenceType::Union => " <<union>>",
+            DependenceType::Type => " <<type>>",
+            _ => "",
+        };
+        let members = typemap
+            .info(&name)
+            .map(|i| member_lines(*kind == DependenceType::Enum, &i.members))
+            .unwrap_or_default();
+
+        *out += &format!("{indent}{decl} {}{stereotype}", id(&name));
+        if members.is_empty() {
+            *out += "\n";
+        } else {
+            *out += " {\n";
+            for m in members {
+                *out += &format!("{indent}  {m}\n");
+            }
+            *out += &format!("{indent}}}\n");
+        }
+    }
+    for (module, child) in &package.children {
+        *out += &format!("{indent}package {module} {{\n");
+        write_package(typemap, child, depth + 1, out);
+        *out += &format!("{indent}}}\n");
+    }
+}
+
+/// One line per field, or per variant for enums
+fn member_lines(is_enum: bool, members: &[Member]) -> Vec<String> {
+    if !is_enum {
+        return members
+            .iter()
+            .map(|m| format!("{} : {}", m.name, m.ty))
+            .collect();
+    }
+
+    let mut variants: Vec<(&str, Vec<&Member>)> = vec![];
+    for m in members {
+        let variant = m.variant.as_deref().unwrap_or_default();
+        match variants.last_mut() {
+            Some((v, fields)) if *v == variant => fields.push(m),
+            _ => variants.push((variant, vec![m])),
+        }
+    }
+    variants
+        .into_iter()
+        .map(|(variant, fields)| {
+            let fields = fields
+                .iter()
+                .filter(|m| !m.name.is_empty())
+                .map(|m| match m.name.parse::<usize>() {
+                    Ok(_) => m.ty.clone(),
+                    Err(_) => format!("{}: {}", m.name, m.ty),
+                })
+                .collect::<Vec<_>>();
+            if fields.is_empty() {
+                variant.to_string()
+            } else {
+                format!("{variant}({})", fields.join(", "))
+            }
+        })
+        .collect()
+}
+
+/// Quote names that aren't plain identifiers, like `std::rc::Rc`
+fn id(name: &str) -> String {
+    if name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        name.into()
+    } else {
+        format!("\"{name}\"")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_class_diagram() {
+        let tm = TypeMap::build("examples/ex14.rs").unwrap();
+        let uml = generate_plantuml(&tm);
+        assert!(uml.starts_with("@startuml\n"));
+        assert!(uml.ends_with("@enduml\n"));
+        assert!(uml.contains("interface Shape\n"));
+        assert!(uml.contains(
+            "enum Polygon {\n  Triangle([Point; 3])\n  Square(corner: Point, side: f64)\n  Empty\n}\n"
+        ));
+        assert!(uml.contains("struct Canvas {\n  shapes : Vec<S>\n  origin : Point\n}\n"));
+        assert!(uml.contains("Circle ..|> Shape\n"));
+        assert!(uml.contains("Canvas *-- Point : origin\n"));
+    }
+
+    #[test]
+    fn test_packages() {
+        let tm = TypeMap::build("examples/ex09.rs").unwrap();
+        let uml = generate_plantuml(&tm);
+        assert!(uml.contains("struct A {\n  0 : m::B\n}\npackage m {\n  struct B\n}\n"));
+        assert!(uml.contains("A *-- B : 0\n"));
+        assert!(!uml.contains("\"m::B\""));
+    }
+}
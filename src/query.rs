@@ -0,0 +1,332 @@
+//! Reachability queries over a `TypeMap`.
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use anyhow::{anyhow, Result};
+
+use super::{DepGraph, Set, TypeMap};
+use crate::rules::matches;
+
+/// Which way to follow the edges of a `TypeMap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From a type to the types it depends on
+    Dependencies,
+    /// From a type to the types that depend on it
+    Dependents,
+}
+
+impl TypeMap {
+    /// The names of every type in the graph, including types that only appear
+    /// as a dependency (like `String` or `i32`).
+    pub fn nodes(&self) -> Set<String> {
+        self.graph
+            .iter()
+            .flat_map(|(src, dests)| {
+                std::iter::once(src.name()).chain(dests.iter().map(|d| d.name()))
+            })
+            .collect()
+    }
+
+    /// Adjacency lists keyed by type name, following edges in the given direction.
+    pub fn adjacency(&self, dir: Direction) -> BTreeMap<String, Set<String>> {
+        let mut adj: BTreeMap<String, Set<String>> =
+            self.nodes().into_iter().map(|n| (n, Set::new())).collect();
+        for (src, dests) in &self.graph {
+            for dest in dests {
+                let (from, to) = match dir {
+                    Direction::Dependencies => (src.name(), dest.name()),
+                    Direction::Dependents => (dest.name(), src.name()),
+                };
+                adj.entry(from).or_default().insert(to);
+            }
+        }
+        adj
+    }
+
+    /// All types reachable from `root` by following edges in `dir`, including
+    /// `root` itself. If `depth` is given, stop after that many hops.
+    pub fn reachable(
+        &self,
+        root: &str,
+        dir: Direction,
+        depth: Option<usize>,
+    ) -> Result<Set<String>> {
+        let adj = self.adjacency(dir);
+        if !adj.contains_key(root) {
+            return Err(anyhow!("no type named `{root}` in the typemap"));
+        }
+
+        let mut seen = Set::from([root.to_string()]);
+        let mut queue = VecDeque::from([(root.to_string(), 0)]);
+        while let Some((n, d)) = queue.pop_front() {
+            if depth.is_some_and(|max| d >= max) {
+                continue;
+            }
+            for next in &adj[&n] {
+                if seen.insert(next.clone()) {
+                    queue.push_back((next.clone(), d + 1));
+                }
+            }
+        }
+        Ok(seen)
+    }
+
+    /// The transitive dependencies of `root` (what it pulls in).
+    pub fn dependencies(&self, root: &str, depth: Option<usize>) -> Result<Set<String>> {
+        self.reachable(root, Direction::Dependencies, depth)
+    }
+
+    /// The transitive dependents of `root` (what breaks if it changes).
+    pub fn dependents(&self, root: &str, depth: Option<usize>) -> Result<Set<String>> {
+        self.reachable(root, Direction::Dependents, depth)
+    }
+
+    /// The subgraph induced by the given type names, keeping only the trait
+    /// implementations between them.
+    pub fn subgraph(&self, names: &Set<String>) -> TypeMap {
+        let graph = self
+            .graph
+            .iter()
+            .filter(|(src, _)| names.contains(&src.name()))
+            .map(|(src, dests)| {
+                let dests = dests
+                    .iter()
+                    .filter(|d| names.contains(&d.name()))
+                    .cloned()
+                    .collect();
+                (src.clone(), dests)
+            })
+            .collect::<DepGraph>();
+        let info = self
+            .info
+            .iter()
+            .filter(|(name, _)| names.contains(*name))
+            .map(|(name, info)| {
+                let mut info = info.clone();
+                info.impls.retain(|tr| names.contains(tr));
+                (name.clone(), info)
+            })
+            .collect();
+        TypeMap {
+            graph,
+            info,
+            diagnostics: self.diagnostics.clone(),
+        }
+    }
+
+    /// The subgraph without types matching any of `patterns`, like `String`
+    /// or `std::*`. Patterns match names and paths as in `crate::rules`.
+    pub fn hide(&self, patterns: &[String]) -> TypeMap {
+        let names = self
+            .nodes()
+            .into_iter()
+            .chain(self.realizations().into_iter().map(|(_, tr)| tr))
+            .filter(|name| {
+                let path = self.path(name);
+                !patterns
+                    .iter()
+                    .any(|p| matches(p, name) || matches(p, &path))
+            })
+            .collect();
+        self.subgraph(&names)
+    }
+
+    /// The shortest dependency path from `from` to `to`, if there is one. When
+    /// they're the same type, that's the shortest cycle through it.
+    pub fn shortest_path(&self, from: &str, to: &str) -> Result<Option<Vec<Hop>>> {
+        let adj = self.sorted_adjacency(from, to)?;
+
+        // `to` is checked as it's reached rather than when it's visited, so
+        // that a path from a type to itself has at least one hop
+        let mut parent: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(n) = queue.pop_front() {
+            for next in &adj[n] {
+                if next == to {
+                    let mut names = vec![to, n];
+                    while let Some(p) = parent.get(names.last().unwrap()) {
+                        names.push(p);
+                    }
+                    names.reverse();
+                    return Ok(Some(self.hops(&names)));
+                }
+                if next != from && !parent.contains_key(next.as_str()) {
+                    parent.insert(next, n);
+                    queue.push_back(next);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Every simple dependency path from `from` to `to` of at most `max_hops`
+    /// edges, shortest first.
+    pub fn all_paths(&self, from: &str, to: &str, max_hops: usize) -> Result<Vec<Vec<Hop>>> {
+        let adj = self.sorted_adjacency(from, to)?;
+
+        let mut paths = vec![];
+        let mut stack = vec![from];
+        Self::walk_paths(&adj, to, max_hops, &mut stack, &mut paths);
+        paths.sort_by_key(Vec::len);
+        Ok(paths.iter().map(|names| self.hops(names)).collect())
+    }
+
+    fn walk_paths<'a>(
+        adj: &'a BTreeMap<String, Vec<String>>,
+        to: &str,
+        max_hops: usize,
+        stack: &mut Vec<&'a str>,
+        paths: &mut Vec<Vec<&'a str>>,
+    ) {
+        let n = *stack.last().unwrap();
+        if n == to && stack.len() > 1 {
+            paths.push(stack.clone());
+            return;
+        }
+        if stack.len() > max_hops {
+            return;
+        }
+        for next in &adj[n] {
+            if next == to || !stack.contains(&next.as_str()) {
+                stack.push(next);
+                Self::walk_paths(adj, to, max_hops, stack, paths);
+                stack.pop();
+            }
+        }
+    }
+
+    /// Dependency adjacency lists in name order, after checking both ends exist
+    fn sorted_adjacency(&self, from: &str, to: &str) -> Result<BTreeMap<String, Vec<String>>> {
+        let adj = self.adjacency(Direction::Dependencies);
+        for name in [from, to] {
+            if !adj.contains_key(name) {
+                return Err(anyhow!("no type named `{name}` in the typemap"));
+            }
+        }
+        Ok(adj
+            .into_iter()
+            .map(|(n, next)| (n, Vec::from_iter(next)))
+            .collect())
+    }
+
+    /// Annotate consecutive pairs of names with the fields that link them
+    fn hops(&self, names: &[&str]) -> Vec<Hop> {
+        names
+            .windows(2)
+            .map(|pair| Hop {
+                from: pair[0].into(),
+                to: pair[1].into(),
+                via: self.edge_causes(pair[0], pair[1]),
+            })
+            .collect()
+    }
+}
+
+/// One edge along a dependency path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hop {
+    pub from: String,
+    pub to: String,
+    /// The fields or generic bounds of `from` that cause the dependence
+    pub via: Vec<String>,
+}
+
+/// Format a path as `A -[b]-> B -[c]-> C`
+pub fn path_to_string(path: &[Hop]) -> String {
+    let Some(first) = path.first() else {
+        return String::new();
+    };
+    path.iter().fold(first.from.clone(), |acc, hop| {
+        format!("{acc} -[{}]-> {}", hop.via.join(", "), hop.to)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(names: &[&str]) -> Set<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let tm = TypeMap::build("examples/ex03.rs").unwrap();
+        assert_eq!(tm.dependencies("A", None).unwrap(), set(&["A", "B", "C"]));
+        assert_eq!(tm.dependencies("A", Some(1)).unwrap(), set(&["A", "B"]));
+        assert_eq!(tm.dependencies("C", None).unwrap(), set(&["C"]));
+    }
+
+    #[test]
+    fn test_dependents() {
+        let tm = TypeMap::build("examples/ex05.rs").unwrap();
+        assert_eq!(tm.dependents("D", None).unwrap(), set(&["A", "C", "D"]));
+        assert_eq!(tm.dependents("D", Some(0)).unwrap(), set(&["D"]));
+        assert!(tm.dependents("Nope", None).is_err());
+    }
+
+    #[test]
+    fn test_qualified_paths() {
+        // `crate::`, `self::` and `super::` paths name the types they lead to
+        let tm = TypeMap::build("examples/ex24.rs").unwrap();
+        assert_eq!(tm.nodes(), set(&["Invoice", "Mailer", "Order"]));
+        assert_eq!(
+            tm.dependents("Mailer", None).unwrap(),
+            set(&["Invoice", "Mailer", "Order"])
+        );
+        let path = tm.shortest_path("Order", "Mailer").unwrap().unwrap();
+        assert_eq!(path_to_string(&path), "Order -[mailer]-> Mailer");
+        let order = tm.metrics().types.into_iter().find(|t| t.name == "Order");
+        let order = order.unwrap();
+        assert_eq!((order.fan_out, order.closure), (2, 2));
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let tm = TypeMap::build("examples/ex05.rs").unwrap();
+        let sub = tm.subgraph(&tm.dependencies("C", None).unwrap());
+        assert_eq!(sub.nodes(), set(&["C", "D", "i32", "usize"]));
+        assert_eq!(sub.graph().len(), 2);
+    }
+
+    #[test]
+    fn test_hide() {
+        let tm = TypeMap::build("examples/ex18.rs").unwrap();
+        let hidden = tm.hide(&["sqlx::*".into(), "infra::*".into(), "Customer".into()]);
+        assert_eq!(hidden.nodes(), set(&["Order", "Store"]));
+        assert!(hidden.info("Mailer").is_none());
+        assert_eq!(tm.hide(&[]).nodes(), tm.nodes());
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let tm = TypeMap::build("examples/ex05.rs").unwrap();
+        let path = tm.shortest_path("A", "i32").unwrap().unwrap();
+        assert_eq!(path_to_string(&path), "A -[C.0]-> C -[d]-> D -[d1]-> i32");
+        assert_eq!(tm.shortest_path("D", "A").unwrap(), None);
+
+        let tm = TypeMap::build("examples/ex08.rs").unwrap();
+        let path = tm.shortest_path("A", "C").unwrap().unwrap();
+        assert_eq!(path_to_string(&path), "A -[T: C]-> C");
+
+        // From a type to itself is the shortest cycle through it
+        let tm = TypeMap::build("examples/ex13.rs").unwrap();
+        let path = tm.shortest_path("A", "A").unwrap().unwrap();
+        assert_eq!(path_to_string(&path), "A -[b]-> B -[a]-> A");
+        let path = tm.shortest_path("E", "E").unwrap().unwrap();
+        assert_eq!(path_to_string(&path), "E -[e]-> E");
+        assert_eq!(tm.shortest_path("F", "F").unwrap(), None);
+    }
+
+    #[test]
+    fn test_all_paths() {
+        let tm = TypeMap::build("examples/ex11.rs").unwrap();
+        let paths = tm.all_paths("A", "F", 3).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0][0].via, vec!["f"]);
+
+        let tm = TypeMap::build("examples/ex10.rs").unwrap();
+        let paths = tm.all_paths("A", "A", 3).unwrap();
+        assert_eq!(path_to_string(&paths[0]), "A -[0]-> A");
+    }
+}
@@ -0,0 +1,281 @@
+//! Architecture rules checked against a `TypeMap`.
+//!
+//! Rules are written in TOML:
+//!
+//! ```toml
+//! # Types in `domain` must not depend on types in `infra`
+//! [[forbid]]
+//! from = "domain::*"
+//! to = "infra::*"
+//!
+//! # Only `db` may use sqlx
+//! [[forbid]]
+//! from = "*"
+//! to = "sqlx::*"
+//! except = ["db::*"]
+//! reason = "database access goes through `db`"
+//!
+//! [limits]
+//! max_fan_out = 10
+//! max_fan_in = 20
+//! max_depth = 6
+//! forbid_cycles = true
+//! ```
+//!
+//! Patterns match the path of a type: `module::Name` for user-defined types,
+//! and the path as written for others, like `sqlx::PgPool` or `String`. `*`
+//! matches any sequence of characters, `::` included.
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::{Span, TypeMap};
+use crate::metrics::TypeMetrics;
+
+/// A set of rules, as described in this module
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    #[serde(default)]
+    pub forbid: Vec<Forbid>,
+    #[serde(default)]
+    pub limits: Limits,
+}
+
+/// Types matching `from` must not depend on types matching `to`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Forbid {
+    pub from: String,
+    pub to: String,
+    /// Patterns for types the rule doesn't apply to, even if they match `from`
+    #[serde(default)]
+    pub except: Vec<String>,
+    /// Shown next to violations
+    pub reason: Option<String>,
+}
+
+/// Bounds on the coupling metrics of each type
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limits {
+    pub max_fan_out: Option<usize>,
+    pub max_fan_in: Option<usize>,
+    pub max_depth: Option<usize>,
+    /// Reject types that (transitively) depend on themselves
+    #[serde(default)]
+    pub forbid_cycles: bool,
+}
+
+/// Reads one of the metrics bounded by `Limits`
+type Metric = fn(&TypeMetrics) -> usize;
+
+/// A broken rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The rule, like `domain::* -> infra::*` or `max_fan_out = 10`
+    pub rule: String,
+    /// What breaks it, like `domain::Order -> infra::Mailer` for an edge or
+    /// `domain::Order` for a type. Unlike `message`, this stays the same while
+    /// the violation does, which is what baselines rely on.
+    pub subject: String,
+    pub message: String,
+    /// Where the offending type is defined
+    pub span: Option<Span>,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{span}: ")?;
+        }
+        write!(f, "{} (rule `{}`)", self.message, self.rule)
+    }
+}
+
+impl Rules {
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Add the rules of another set. Its limits take precedence.
+    pub fn extend(&mut self, other: Rules) {
+        self.forbid.extend(other.forbid);
+        let (limits, other) = (&mut self.limits, other.limits);
+        limits.max_fan_out = other.max_fan_out.or(limits.max_fan_out);
+        limits.max_fan_in = other.max_fan_in.or(limits.max_fan_in);
+        limits.max_depth = other.max_depth.or(limits.max_depth);
+        limits.forbid_cycles |= other.forbid_cycles;
+    }
+}
+
+impl Forbid {
+    fn applies(&self, from: &str, to: &str) -> bool {
+        matches(&self.from, from)
+            && matches(&self.to, to)
+            && !self.except.iter().any(|e| matches(e, from))
+    }
+}
+
+impl TypeMap {
+    /// Every violation of `rules`, in the order the rules are declared and
+    /// then by type name
+    pub fn check(&self, rules: &Rules) -> Vec<Violation> {
+        let span = |name: &str| self.info(name).and_then(|i| i.span.clone());
+        let mut violations = vec![];
+
+        let edges = self.sorted_edges();
+        for rule in &rules.forbid {
+            for (src, dest) in &edges {
+                let (from, to) = (self.path(&src.name()), self.path(&dest.name()));
+                if !rule.applies(&from, &to) {
+                    continue;
+                }
+                let via = self.dependence_causes(&src.name(), dest);
+                let mut message = format!("`{from}` depends on `{to}`");
+                if !via.is_empty() {
+                    message += &format!(" via {}", via.join(", "));
+                }
+                if let Some(reason) = &rule.reason {
+                    message += &format!(": {reason}");
+                }
+                violations.push(Violation {
+                    rule: format!("{} -> {}", rule.from, rule.to),
+                    subject: format!("{from} -> {to}"),
+                    message,
+                    span: span(&src.name()),
+                });
+            }
+        }
+
+        let limits = &rules.limits;
+        let metrics = self.metrics();
+        let bounds: [(&str, Option<usize>, Metric); 3] = [
+            ("fan-out", limits.max_fan_out, |t| t.fan_out),
+            ("fan-in", limits.max_fan_in, |t| t.fan_in),
+            ("depth", limits.max_depth, |t| t.depth),
+        ];
+        for (name, max, metric) in bounds {
+            let Some(max) = max else { continue };
+            for t in metrics.types.iter().filter(|t| metric(t) > max) {
+                violations.push(Violation {
+                    rule: format!("max_{} = {max}", name.replace('-', "_")),
+                    subject: self.path(&t.name),
+                    message: format!("`{}` has a {name} of {}", self.path(&t.name), metric(t)),
+                    span: span(&t.name),
+                });
+            }
+        }
+
+        if limits.forbid_cycles {
+            for cycle in self.cycles() {
+                violations.push(Violation {
+                    rule: "forbid_cycles".into(),
+                    subject: cycle.types.join(", "),
+                    message: format!("cycle between {}", cycle.types.join(", ")),
+                    span: span(&cycle.types[0]),
+                });
+            }
+        }
+        violations
+    }
+
+    /// The path rules match a type by: `module::Name` for user-defined types,
+    /// and the path as written without `crate::` for others
+    pub(crate) fn path(&self, name: &str) -> String {
+        match self.info(name) {
+            Some(info) if !info.module.is_empty() => format!("{}::{name}", info.module),
+            Some(_) => name.to_string(),
+            None => name.strip_prefix("crate::").unwrap_or(name).to_string(),
+        }
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any sequence
+pub(crate) fn matches(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|&i| text.is_char_boundary(i))
+                .any(|i| matches(rest, &text[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("domain::*", "domain::Order"));
+        assert!(matches("domain::*", "domain::sub::Order"));
+        assert!(!matches("domain::*", "domainx::Order"));
+        assert!(matches("*::Order", "domain::Order"));
+        assert!(matches("*", "String"));
+        assert!(matches("String", "String"));
+        assert!(!matches("String", "Strings"));
+    }
+
+    #[test]
+    fn test_check() {
+        let tm = TypeMap::build("examples/ex18.rs").unwrap();
+        let rules = Rules::from_toml(
+            r#"
+            [[forbid]]
+            from = "domain::*"
+            to = "infra::*"
+
+            [[forbid]]
+            from = "*"
+            to = "sqlx::*"
+            except = ["db::*"]
+            reason = "database access goes through `db`"
+
+            [limits]
+            max_fan_out = 1
+            "#,
+        )
+        .unwrap();
+        let violations = tm.check(&rules);
+        let lines = violations.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "examples/ex18.rs:8:16: `domain::Order` depends on `infra::Mailer` via mailer \
+                 (rule `domain::* -> infra::*`)",
+                "examples/ex18.rs:15:16: `infra::Mailer` depends on `sqlx::PgPool` via pool: \
+                 database access goes through `db` (rule `* -> sqlx::*`)",
+                "examples/ex18.rs:8:16: `domain::Order` has a fan-out of 2 (rule `max_fan_out = 1`)",
+            ]
+        );
+
+        assert!(tm.check(&Rules::default()).is_empty());
+
+        let mut merged = Rules::from_toml("[limits]\nmax_fan_out = 1\nmax_depth = 3").unwrap();
+        merged.extend(rules);
+        assert_eq!(merged.forbid.len(), 2);
+        assert_eq!(merged.limits.max_depth, Some(3));
+        assert!(Rules::from_toml("[[forbid]]\nfrom = \"a\"").is_err());
+
+        // Qualified paths don't get around the rules
+        let tm = TypeMap::build("examples/ex24.rs").unwrap();
+        let rules =
+            Rules::from_toml("[[forbid]]\nfrom = \"domain::*\"\nto = \"infra::*\"").unwrap();
+        let subjects = tm
+            .check(&rules)
+            .into_iter()
+            .map(|v| v.subject)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            subjects,
+            [
+                "domain::billing::Invoice -> infra::Mailer",
+                "domain::Order -> infra::Mailer",
+            ]
+        );
+    }
+}
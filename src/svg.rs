@@ -0,0 +1,213 @@
+//! Pure-Rust SVG rendering for machines without graphviz installed.
+//!
+//! Only the subset of DOT that `dot::dot_graph` emits is understood: node
+//! labels are reduced to plain text and subgraphs are flattened.
+use std::collections::HashMap;
+
+use graphviz_rust::dot_structures::*;
+use layout::backends::svg::SVGWriter;
+use layout::core::base::Orientation;
+use layout::core::color::Color;
+use layout::core::style::{LineStyleKind, StyleAttr};
+use layout::std_shapes::render::get_shape_size;
+use layout::std_shapes::shapes::{Arrow, Element, LineEndKind, ShapeKind};
+use layout::topo::layout::VisualGraph;
+
+/// Lay out and draw a DOT graph as an SVG document
+pub fn render_svg(graph: &Graph) -> String {
+    let stmts = match graph {
+        Graph::Graph { stmts, .. } | Graph::DiGraph { stmts, .. } => stmts,
+    };
+
+    let mut nodes = vec![];
+    let mut edges = vec![];
+    collect(stmts, &mut nodes, &mut edges);
+
+    let orientation = match graph_attr(stmts, "rankdir").as_deref() {
+        Some("LR") | Some("RL") => Orientation::LeftToRight,
+        _ => Orientation::TopToBottom,
+    };
+    let mut vg = VisualGraph::new(orientation);
+    let mut handles = HashMap::new();
+    let mut add_node = |vg: &mut VisualGraph, name: &str, attrs: &[Attribute]| {
+        if let Some(&h) = handles.get(name) {
+            return h;
+        }
+        let label = attr(attrs, "label")
+            .map(|l| plain_text(&l))
+            .unwrap_or(name.into());
+        let look = StyleAttr::new(
+            Color::fast(attr(attrs, "color").as_deref().unwrap_or("black")),
+            attr(attrs, "penwidth")
+                .and_then(|w| w.parse().ok())
+                .unwrap_or(1),
+            Some(Color::fast(
+                attr(attrs, "fillcolor").as_deref().unwrap_or("white"),
+            )),
+            0,
+            14,
+        );
+        let shape = ShapeKind::new_box(&label);
+        let size = get_shape_size(orientation, &shape, look.font_size, false);
+        let h = vg.add_node(Element::create(shape, look, orientation, size));
+        handles.insert(name.to_string(), h);
+        h
+    };
+
+    for (name, attrs) in &nodes {
+        add_node(&mut vg, name, attrs);
+    }
+    for (src, dest, attrs) in &edges {
+        let from = add_node(&mut vg, src, &[]);
+        let to = add_node(&mut vg, dest, &[]);
+        let look = StyleAttr::new(
+            Color::fast(attr(attrs, "color").as_deref().unwrap_or("black")),
+            attr(attrs, "penwidth")
+                .and_then(|w| w.parse().ok())
+                .unwrap_or(1),
+            None,
+            0,
+            12,
+        );
+        let style = match attr(attrs, "style").as_deref() {
+            Some("dashed") => LineStyleKind::Dashed,
+            Some("dotted") => LineStyleKind::Dotted,
+            _ => LineStyleKind::Normal,
+        };
+        let label = attr(attrs, "label")
+            .map(|l| plain_text(&l))
+            .unwrap_or_default();
+        let arrow = Arrow::new(
+            LineEndKind::None,
+            LineEndKind::Arrow,
+            style,
+            &label,
+            &look,
+            &None,
+            &None,
+        );
+        vg.add_edge(arrow, from, to);
+    }
+
+    let mut svg = SVGWriter::new();
+    vg.do_it(false, false, false, &mut svg);
+    sort_styles(svg.finalize())
+}
+
+/// layout-rs writes its font classes in hash order; sort them so the same
+/// graph always renders to the same bytes
+fn sort_styles(svg: String) -> String {
+    let (Some(start), Some(end)) = (svg.find("<style>\n"), svg.find("</style>")) else {
+        return svg;
+    };
+    let start = start + "<style>\n".len();
+    let mut rules = svg[start..end].lines().collect::<Vec<_>>();
+    rules.sort();
+    let mut sorted = svg[..start].to_string();
+    for rule in rules {
+        sorted += rule;
+        sorted += "\n";
+    }
+    sorted + &svg[end..]
+}
+
+type NodeStmt = (String, Vec<Attribute>);
+type EdgeStmt = (String, String, Vec<Attribute>);
+
+/// Gather node and edge statements, flattening subgraphs
+fn collect(stmts: &[Stmt], nodes: &mut Vec<NodeStmt>, edges: &mut Vec<EdgeStmt>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Node(Node { id, attributes }) => nodes.push((id_str(&id.0), attributes.clone())),
+            Stmt::Edge(Edge {
+                ty: EdgeTy::Pair(Vertex::N(src), Vertex::N(dest)),
+                attributes,
+            }) => edges.push((id_str(&src.0), id_str(&dest.0), attributes.clone())),
+            Stmt::Edge(Edge {
+                ty: EdgeTy::Chain(vertices),
+                attributes,
+            }) => {
+                let names = vertices
+                    .iter()
+                    .filter_map(|v| match v {
+                        Vertex::N(n) => Some(id_str(&n.0)),
+                        Vertex::S(_) => None,
+                    })
+                    .collect::<Vec<_>>();
+                for pair in names.windows(2) {
+                    edges.push((pair[0].clone(), pair[1].clone(), attributes.clone()));
+                }
+            }
+            Stmt::Subgraph(Subgraph { stmts, .. }) => collect(stmts, nodes, edges),
+            _ => {}
+        }
+    }
+}
+
+fn graph_attr(stmts: &[Stmt], name: &str) -> Option<String> {
+    stmts.iter().find_map(|stmt| match stmt {
+        Stmt::Attribute(a) => attr(std::slice::from_ref(a), name),
+        Stmt::GAttribute(GraphAttributes::Graph(attrs)) => attr(attrs, name),
+        _ => None,
+    })
+}
+
+fn attr(attrs: &[Attribute], name: &str) -> Option<String> {
+    attrs
+        .iter()
+        .find(|Attribute(k, _)| id_str(k) == name)
+        .map(|Attribute(_, v)| id_str(v))
+}
+
+fn id_str(id: &Id) -> String {
+    match id {
+        Id::Html(s) | Id::Escaped(s) | Id::Plain(s) | Id::Anonymous(s) => s
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .unwrap_or(s)
+            .replace("\\\"", "\""),
+    }
+}
+
+/// The text of an HTML-like label, without its markup. Table rows are
+/// separated by `|`.
+fn plain_text(label: &str) -> String {
+    let mut text = String::new();
+    let mut tag = None::<String>;
+    let inner = label
+        .strip_prefix('<')
+        .and_then(|l| l.strip_suffix('>'))
+        .unwrap_or(label);
+    for c in inner.chars() {
+        match (c, &mut tag) {
+            ('<', None) => tag = Some(String::new()),
+            ('>', Some(t)) => {
+                if t.starts_with("tr") && !text.is_empty() {
+                    text += " | ";
+                }
+                tag = None;
+            }
+            (c, Some(t)) => t.push(c),
+            (c, None) => text.push(c),
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dot::{dot_graph, DotOptions};
+    use crate::TypeMap;
+
+    #[test]
+    fn test_render_svg() {
+        let tm = TypeMap::build("examples/ex18.rs").unwrap();
+        let svg = render_svg(&dot_graph(&tm, &DotOptions::default()));
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("struct Store"));
+        assert!(svg.contains("sqlx::PgPool"));
+    }
+}
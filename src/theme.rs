@@ -0,0 +1,273 @@
+//! Colors, fonts and shapes used by the renderers.
+//!
+//! A theme is built in code from a preset like `Theme::dark()`, or loaded
+//! from TOML with `Theme::from_toml`. Every key is optional and falls back to
+//! the preset named by the top-level `preset` key, `light` by default:
+//!
+//! ```toml
+//! preset = "dark"
+//! rankdir = "LR"
+//! font = "Fira Code"
+//!
+//! [nodes.trait]
+//! shape = "ellipse"
+//! accent = "cyan"
+//!
+//! [edges.impl]
+//! style = "dotted"
+//! arrowhead = "empty"
+//! ```
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::DependenceType;
+
+/// How a `TypeMap` is drawn
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Direction dependencies point in: `TB`, `LR`, `BT` or `RL`
+    pub rankdir: String,
+    pub background: String,
+    /// Color of names, borders, edges and labels
+    pub foreground: String,
+    pub font: String,
+    /// Border color of module clusters
+    pub cluster: String,
+    pub nodes: NodeStyles,
+    pub edges: EdgeStyles,
+}
+
+/// Node styles by kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeStyles {
+    pub r#struct: NodeStyle,
+    pub r#enum: NodeStyle,
+    pub r#union: NodeStyle,
+    pub r#type: NodeStyle,
+    pub r#trait: NodeStyle,
+    pub temp: NodeStyle,
+    /// Types that are only depended on, like `String`
+    pub external: NodeStyle,
+    /// Collapsed modules
+    pub module: NodeStyle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeStyle {
+    /// Graphviz shape, like `rect` or `ellipse`. Ignored for record nodes.
+    pub shape: String,
+    /// Color of the kind keyword in front of the name
+    pub accent: String,
+    /// Border color, or empty for the theme's foreground
+    pub color: String,
+    /// Fill color, or empty for none
+    pub fill: String,
+}
+
+/// Edge styles by kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EdgeStyles {
+    /// A field containing the type
+    pub field: EdgeStyle,
+    /// A trait bound on a type parameter
+    pub r#trait: EdgeStyle,
+    /// An `impl Trait for Type` block
+    pub r#impl: EdgeStyle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EdgeStyle {
+    /// Color, or empty for the theme's foreground
+    pub color: String,
+    /// `solid`, `dashed`, `dotted` or `bold`
+    pub style: String,
+    /// Graphviz arrow shape, like `normal`, `empty` or `diamond`
+    pub arrowhead: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+impl Default for NodeStyles {
+    fn default() -> Self {
+        Theme::light().nodes
+    }
+}
+
+impl Default for NodeStyle {
+    fn default() -> Self {
+        Self::new("rect", "")
+    }
+}
+
+impl Default for EdgeStyles {
+    fn default() -> Self {
+        Theme::light().edges
+    }
+}
+
+impl Default for EdgeStyle {
+    fn default() -> Self {
+        Self::new("solid", "normal")
+    }
+}
+
+impl NodeStyle {
+    fn new(shape: &str, accent: &str) -> Self {
+        Self {
+            shape: shape.into(),
+            accent: accent.into(),
+            color: String::new(),
+            fill: String::new(),
+        }
+    }
+}
+
+impl EdgeStyle {
+    fn new(style: &str, arrowhead: &str) -> Self {
+        Self {
+            color: String::new(),
+            style: style.into(),
+            arrowhead: arrowhead.into(),
+        }
+    }
+}
+
+impl NodeStyles {
+    /// The style of a user-defined type
+    pub fn of(&self, kind: &DependenceType) -> &NodeStyle {
+        match kind {
+            DependenceType::Struct => &self.r#struct,
+            DependenceType::Enum => &self.r#enum,
+            DependenceType::Union => &self.r#union,
+            DependenceType::Type => &self.r#type,
+            DependenceType::Trait => &self.r#trait,
+            DependenceType::Temp => &self.temp,
+        }
+    }
+}
+
+impl Theme {
+    /// Dark text on white
+    pub fn light() -> Self {
+        Self {
+            rankdir: "TB".into(),
+            background: "white".into(),
+            foreground: "black".into(),
+            font: "monospace".into(),
+            cluster: "gray".into(),
+            nodes: NodeStyles {
+                r#struct: NodeStyle::new("rect", "red"),
+                r#enum: NodeStyle::new("rect", "orange"),
+                r#union: NodeStyle::new("rect", "green"),
+                r#type: NodeStyle::new("rect", "purple"),
+                r#trait: NodeStyle::new("rect", "blue"),
+                temp: NodeStyle::new("rect", "gray"),
+                external: NodeStyle::new("ellipse", ""),
+                module: NodeStyle::new("folder", "gray"),
+            },
+            edges: EdgeStyles {
+                field: EdgeStyle::new("solid", "normal"),
+                r#trait: EdgeStyle::new("dashed", "normal"),
+                r#impl: EdgeStyle::new("dashed", "empty"),
+            },
+        }
+    }
+
+    /// Light text on a dark gray background
+    pub fn dark() -> Self {
+        let light = Self::light();
+        Self {
+            background: "#1e1e1e".into(),
+            foreground: "#d4d4d4".into(),
+            cluster: "#808080".into(),
+            nodes: NodeStyles {
+                r#struct: NodeStyle::new("rect", "#f48771"),
+                r#enum: NodeStyle::new("rect", "#e5c07b"),
+                r#union: NodeStyle::new("rect", "#98c379"),
+                r#type: NodeStyle::new("rect", "#c678dd"),
+                r#trait: NodeStyle::new("rect", "#61afef"),
+                temp: NodeStyle::new("rect", "#808080"),
+                ..light.nodes
+            },
+            ..light
+        }
+    }
+
+    /// A preset by name: `light` or `dark`
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(Self::light()),
+            "dark" => Some(Self::dark()),
+            _ => None,
+        }
+    }
+
+    /// Load a theme in the format described in this module
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let mut overrides: toml::Table = toml.parse()?;
+        let base = match overrides.remove("preset") {
+            None => Self::light(),
+            Some(toml::Value::String(name)) => {
+                Self::preset(&name).ok_or_else(|| anyhow!("unknown theme preset `{name}`"))?
+            }
+            Some(other) => bail!("theme preset must be a string, not `{other}`"),
+        };
+        let mut table = toml::Table::try_from(base)?;
+        merge(&mut table, overrides);
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+}
+
+/// Recursively overwrite `base` with the keys of `overrides`
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(o)) => merge(b, o),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let theme = Theme::from_toml(
+            r#"
+            preset = "dark"
+            rankdir = "LR"
+
+            [nodes.trait]
+            accent = "cyan"
+
+            [edges.impl]
+            style = "dotted"
+            "#,
+        )
+        .unwrap();
+        let dark = Theme::dark();
+        assert_eq!(theme.rankdir, "LR");
+        assert_eq!(theme.background, dark.background);
+        assert_eq!(theme.nodes.r#trait.accent, "cyan");
+        assert_eq!(theme.nodes.r#trait.shape, "rect");
+        assert_eq!(theme.edges.r#impl.style, "dotted");
+        assert_eq!(theme.edges.r#impl.arrowhead, "empty");
+
+        assert_eq!(Theme::from_toml("").unwrap(), Theme::light());
+        assert!(Theme::from_toml("preset = \"neon\"").is_err());
+        assert!(Theme::from_toml("[nodes.strukt]\nshape = \"box\"").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Types that nothing refers to, as candidates for deletion.
+//!
+//! Only uses between types are seen, so a type used just by functions is
+//! reported too. Public types are never reported, since other crates may use
+//! them; `pub(crate)` and narrower ones are. A `pub` type in a private module
+//! counts as private, so one that is re-exported with `pub use` is reported
+//! too.
+use std::fmt;
+
+use super::{DependenceType, Set, Span, TypeMap};
+use crate::query::Direction;
+
+/// A user-defined type that no other type refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unused {
+    pub name: String,
+    pub kind: DependenceType,
+    /// Where the type is defined
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for Unused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{span}: ")?;
+        }
+        let why = match self.kind {
+            DependenceType::Trait => "is never implemented or used as a bound",
+            DependenceType::Type => "is never referenced",
+            _ => "has no dependents",
+        };
+        write!(f, "{} `{}` {why}", self.kind.to_ty(), self.name)
+    }
+}
+
+impl TypeMap {
+    /// Private types, traits and aliases without dependents, sorted by name.
+    /// `pub` types in a private module count as private. A type that only
+    /// refers to itself, like a linked list node, counts as unused.
+    pub fn unused(&self) -> Vec<Unused> {
+        let dependents = self.adjacency(Direction::Dependents);
+        // Implementing a trait uses it without depending on it
+        let implemented = self
+            .realizations()
+            .into_iter()
+            .map(|(_, tr)| tr)
+            .collect::<Set<_>>();
+        let mut unused = self
+            .graph
+            .keys()
+            .filter(|k| *k.kind() != DependenceType::Temp)
+            .filter(|k| {
+                let name = k.name();
+                dependents[&name].iter().all(|d| *d == name)
+                    && !implemented.contains(&name)
+                    && self
+                        .info(&name)
+                        .is_none_or(|i| i.visibility != "pub" || i.in_private_module)
+            })
+            .map(|k| Unused {
+                name: self.path(&k.name()),
+                kind: k.kind().clone(),
+                span: self.info(&k.name()).and_then(|i| i.span.clone()),
+            })
+            .collect::<Vec<_>>();
+        unused.sort_by(|a, b| a.name.cmp(&b.name));
+        unused
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unused() {
+        let tm = TypeMap::build("examples/ex19.rs").unwrap();
+        let lines = tm
+            .unused()
+            .iter()
+            .map(|u| u.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "examples/ex19.rs:11:6: type `Bytes` is never referenced",
+                "examples/ex19.rs:17:17: enum `Leftover` has no dependents",
+                "examples/ex19.rs:21:8: struct `List` has no dependents",
+                "examples/ex19.rs:31:7: trait `Orphan` is never implemented or used as a bound",
+                "examples/ex19.rs:13:8: struct `Scratch` has no dependents",
+                "examples/ex19.rs:35:8: struct `Wrapper` has no dependents",
+                "examples/ex19.rs:46:16: struct `internal::Helper` has no dependents",
+            ]
+        );
+
+        // `pub` types in private modules can't be used by other crates
+        let names = TypeMap::build("examples/ex18.rs")
+            .unwrap()
+            .unused()
+            .into_iter()
+            .map(|u| u.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["db::Store", "domain::Order"]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Watch mode: rebuilding whenever the analyzed files change, and serving the
+//! HTML viewer to a browser that reloads itself after each rebuild.
+//!
+//! Files are polled rather than subscribed to, which is plenty for the few
+//! hundred files of a crate and works the same on every platform. Missing
+//! module files and the directories of the analyzed files are watched too, so
+//! adding `mod name;` along with its file is picked up.
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+
+use crate::input::Cache;
+
+/// How often to check the watched files for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Script added to served pages, reloading them when the version changes
+const RELOAD_SCRIPT: &str = r#"<script>
+(function () {
+  var version = null;
+  setInterval(function () {
+    fetch("/version").then(function (r) { return r.text(); }).then(function (v) {
+      if (version !== null && v !== version) location.reload();
+      version = v;
+    }).catch(function () {});
+  }, 1000);
+})();
+</script>
+"#;
+
+/// Modification time of each watched path, `None` for missing ones
+type Snapshot = BTreeMap<PathBuf, Option<SystemTime>>;
+
+fn snapshot(cache: &Cache) -> Snapshot {
+    cache
+        .watched()
+        .iter()
+        .map(|path| {
+            let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            (path.clone(), modified)
+        })
+        .collect()
+}
+
+/// Run `build` with `cache`, then again whenever a file it looked at changes,
+/// forever. Errors are reported without stopping, since they are often
+/// transient while editing.
+pub fn watch(cache: &mut Cache, mut build: impl FnMut(&mut Cache) -> Result<()>) -> Result<()> {
+    loop {
+        cache.clear_watched();
+        if let Err(e) = build(cache) {
+            eprintln!("error: {e}");
+        }
+        let files = cache.watched().iter().filter(|p| p.is_file()).count();
+        eprintln!("watching {files} file(s) for changes");
+
+        let before = snapshot(cache);
+        while snapshot(cache) == before {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        // Let editors finish writing, possibly several files
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// An HTTP server for the latest version of a page
+#[derive(Clone)]
+pub struct Server {
+    /// Address the server listens on
+    pub addr: SocketAddr,
+    /// Version of the page, bumped on each update, and the page
+    page: Arc<Mutex<(u64, String)>>,
+}
+
+impl Server {
+    /// Start serving on `addr`, like `127.0.0.1:8000`, in the background
+    pub fn start(addr: &str) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let server = Self {
+            addr: listener.local_addr()?,
+            page: Arc::default(),
+        };
+        let handler = server.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A client going away is no reason to stop serving
+                let _ = handler.respond(stream);
+            }
+        });
+        Ok(server)
+    }
+
+    /// Serve `html` from now on, telling open pages to reload
+    pub fn update(&self, html: &str) {
+        let html = match html.rfind("</body>") {
+            Some(end) => format!("{}{RELOAD_SCRIPT}{}", &html[..end], &html[end..]),
+            None => format!("{html}{RELOAD_SCRIPT}"),
+        };
+        let mut page = self.page.lock().unwrap();
+        *page = (page.0 + 1, html);
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> Result<()> {
+        let mut reader = BufReader::new(&stream);
+        let mut request = String::new();
+        reader.read_line(&mut request)?;
+        // Read the headers too, as closing with unread data resets the
+        // connection
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+        let path = request.split_whitespace().nth(1).unwrap_or("/");
+        let (version, page) = self.page.lock().unwrap().clone();
+        let (status, content_type, body) = match path {
+            "/" | "/index.html" => ("200 OK", "text/html; charset=utf-8", page),
+            "/version" => ("200 OK", "text/plain", version.to_string()),
+            _ => ("404 Not Found", "text/plain", "not found".into()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+             Cache-Control: no-store\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use super::*;
+
+    fn get(addr: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: {addr}\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_server() {
+        let server = Server::start("127.0.0.1:0").unwrap();
+        let addr = server.addr.to_string();
+
+        server.update("<html><body>one</body></html>");
+        assert!(get(&addr, "/version").ends_with("\r\n\r\n1"));
+        let page = get(&addr, "/");
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+        assert!(page.contains("one<script>"));
+        assert!(page.ends_with("</script>\n</body></html>"));
+
+        server.update("<html><body>two</body></html>");
+        assert!(get(&addr, "/version").ends_with("\r\n\r\n2"));
+        assert!(get(&addr, "/other").starts_with("HTTP/1.1 404"));
+    }
+}